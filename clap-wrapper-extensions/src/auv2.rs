@@ -42,10 +42,20 @@ pub struct PluginInfoAsAUv2 {
 }
 
 impl PluginInfoAsAUv2 {
+    /// Creates the info from the 4-char AU type and subtype codes.
+    ///
+    /// Like the manufacturer code, both must be exactly 4 ASCII characters,
+    /// so this panics otherwise.
     #[inline]
     pub fn new(au_type: &str, au_subt: &str) -> Self {
-        assert_eq!(au_type.len(), 4, "au_type must be exactly 4 characters long");
-        assert_eq!(au_subt.len(), 4, "au_subt must be exactly 4 characters long");
+        assert!(
+            au_type.len() == 4 && au_type.is_ascii(),
+            "au_type must be exactly 4 ASCII characters long"
+        );
+        assert!(
+            au_subt.len() == 4 && au_subt.is_ascii(),
+            "au_subt must be exactly 4 ASCII characters long"
+        );
 
         let mut inner = clap_plugin_info_as_auv2 {
             au_type: [0; 5],
@@ -59,9 +69,68 @@ impl PluginInfoAsAUv2 {
 
         Self { inner }
     }
+
+    /// The 4-char AU type code, e.g. `b"aufx"`.
+    #[inline]
+    pub fn au_type(&self) -> &[u8] {
+        &self.inner.au_type[..4]
+    }
+
+    /// The 4-char AU subtype code identifying the plugin.
+    #[inline]
+    pub fn au_subtype(&self) -> &[u8] {
+        &self.inner.au_subt[..4]
+    }
+}
+
+/// The manufacturer that all AUv2 plugins of a factory are advertised under.
+///
+/// clap-wrapper reads the manufacturer from the factory, not from the individual plugins,
+/// so plugins of different brands have to be exposed by different factories,
+/// e.g. by building a separate binary per brand.
+#[derive(Debug, Copy, Clone)]
+pub struct AUv2Manufacturer {
+    code: &'static CStr,
+    name: &'static CStr,
+}
+
+impl AUv2Manufacturer {
+    /// Creates a manufacturer from its 4-char code (the AU's `manu` field) and display name.
+    ///
+    /// Hosts like Logic silently refuse to load AUs whose manufacturer code isn't exactly
+    /// 4 ASCII characters, so this panics otherwise.
+    /// When used to initialize a `const`, a wrong code is a compile-time error.
+    #[inline]
+    pub const fn new(code: &'static CStr, name: &'static CStr) -> Self {
+        let code_bytes = code.to_bytes();
+        assert!(
+            code_bytes.len() == 4 && code_bytes.is_ascii(),
+            "manufacturer code must be exactly 4 ASCII characters long"
+        );
+
+        Self { code, name }
+    }
+
+    /// The 4-char manufacturer code, e.g. `c"Frau"`.
+    #[inline]
+    pub const fn code(&self) -> &'static CStr {
+        self.code
+    }
+
+    /// The manufacturer's display name.
+    #[inline]
+    pub const fn name(&self) -> &'static CStr {
+        self.name
+    }
 }
 
 pub trait PluginFactoryAsAUv2 {
+    /// Returns the AUv2 info of the plugin at the given index.
+    ///
+    /// A panic is caught and reported to the host as a missing plugin.
+    /// If the implementation uses interior mutability,
+    /// it must not leave its state inconsistent when panicking,
+    /// as it may be called again afterwards.
     fn get_auv2_info(&self, index: u32) -> Option<PluginInfoAsAUv2>;
 }
 
@@ -78,17 +147,13 @@ unsafe impl<F: PluginFactoryAsAUv2> Factory for PluginFactoryAsAUv2Wrapper<F> {
 }
 
 impl<F: PluginFactoryAsAUv2> PluginFactoryAsAUv2Wrapper<F> {
-    pub const fn new(
-        manufacturer_code: &'static CStr,
-        manufacturer_name: &'static CStr,
-        factory: F,
-    ) -> Self {
+    pub const fn new(manufacturer: AUv2Manufacturer, factory: F) -> Self {
         Self {
             factory,
             raw: clap_plugin_factory_as_auv2 {
                 get_auv2_info: Some(Self::get_auv2_info),
-                manufacturer_code: manufacturer_code.as_ptr(),
-                manufacturer_name: manufacturer_name.as_ptr(),
+                manufacturer_code: manufacturer.code.as_ptr(),
+                manufacturer_name: manufacturer.name.as_ptr(),
             },
         }
     }
@@ -103,14 +168,133 @@ impl<F: PluginFactoryAsAUv2> PluginFactoryAsAUv2Wrapper<F> {
             return false; // HOST_MISBEHAVING
         };
 
+        // Unwinding into the host would be UB, so panics must be caught here.
+        // AssertUnwindSafe is sound because the closure only borrows the factory immutably,
+        // so a panic can't leave anything half-modified on our side (see the trait docs),
+        // and the info is only returned by value.
         let Ok(Some(info_data)) =
             catch_unwind(AssertUnwindSafe(|| factory.factory.get_auv2_info(index)))
         else {
             return false; // Either panicked or returned None.
         };
 
+        // The host's info struct is only written once the info has been fully produced,
+        // so it is left untouched if the call above panicked.
         info.write(info_data.inner);
 
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANUFACTURER: AUv2Manufacturer = AUv2Manufacturer::new(c"Test", c"Test Audio");
+
+    struct TestFactory;
+
+    impl PluginFactoryAsAUv2 for TestFactory {
+        fn get_auv2_info(&self, index: u32) -> Option<PluginInfoAsAUv2> {
+            match index {
+                0 => Some(PluginInfoAsAUv2::new("aufx", "Tst0")),
+                1 => Some(PluginInfoAsAUv2::new("aumu", "Tst1")),
+                _ => None,
+            }
+        }
+    }
+
+    struct PanickingFactory;
+
+    impl PluginFactoryAsAUv2 for PanickingFactory {
+        fn get_auv2_info(&self, _index: u32) -> Option<PluginInfoAsAUv2> {
+            panic!("deliberate panic in get_auv2_info");
+        }
+    }
+
+    /// Calls `get_auv2_info` the way a host does, through the raw factory's function pointer.
+    /// The info is prefilled with a marker, to tell whether it was written.
+    fn call_raw<F: PluginFactoryAsAUv2>(
+        wrapper: &PluginFactoryAsAUv2Wrapper<F>,
+        index: u32,
+    ) -> (bool, clap_plugin_info_as_auv2) {
+        let mut info = clap_plugin_info_as_auv2 {
+            au_type: [0xff; 5],
+            au_subt: [0xff; 5],
+        };
+
+        // Hosts only see a pointer to the raw factory, which is the start of the wrapper.
+        let raw =
+            wrapper as *const PluginFactoryAsAUv2Wrapper<F> as *mut clap_plugin_factory_as_auv2;
+        let get_auv2_info = wrapper.raw.get_auv2_info.unwrap();
+        let found = unsafe { get_auv2_info(raw, index, &mut info) };
+
+        (found, info)
+    }
+
+    #[test]
+    #[should_panic(expected = "au_subt must be exactly 4 ASCII characters long")]
+    fn non_ascii_subtype_is_rejected() {
+        // 4 bytes, but only 3 characters.
+        PluginInfoAsAUv2::new("aufx", "é1a");
+    }
+
+    #[test]
+    fn raw_factory_advertises_the_manufacturer() {
+        let wrapper = PluginFactoryAsAUv2Wrapper::new(MANUFACTURER, TestFactory);
+
+        let code = unsafe { CStr::from_ptr(wrapper.raw.manufacturer_code) };
+        let name = unsafe { CStr::from_ptr(wrapper.raw.manufacturer_name) };
+        assert_eq!(code, c"Test");
+        assert_eq!(name, c"Test Audio");
+    }
+
+    #[test]
+    fn raw_get_auv2_info_writes_each_plugin() {
+        let wrapper = PluginFactoryAsAUv2Wrapper::new(MANUFACTURER, TestFactory);
+
+        let (found, info) = call_raw(&wrapper, 0);
+        assert!(found);
+        assert_eq!(&info.au_type, b"aufx\0");
+        assert_eq!(&info.au_subt, b"Tst0\0");
+
+        let (found, info) = call_raw(&wrapper, 1);
+        assert!(found);
+        assert_eq!(&info.au_type, b"aumu\0");
+        assert_eq!(&info.au_subt, b"Tst1\0");
+    }
+
+    #[test]
+    fn raw_get_auv2_info_rejects_unknown_index() {
+        let wrapper = PluginFactoryAsAUv2Wrapper::new(MANUFACTURER, TestFactory);
+
+        let (found, info) = call_raw(&wrapper, 2);
+        assert!(!found);
+        assert_eq!(info.au_type, [0xff; 5]);
+        assert_eq!(info.au_subt, [0xff; 5]);
+    }
+
+    #[test]
+    fn raw_get_auv2_info_rejects_null_factory() {
+        let wrapper = PluginFactoryAsAUv2Wrapper::new(MANUFACTURER, TestFactory);
+        let get_auv2_info = wrapper.raw.get_auv2_info.unwrap();
+
+        let mut info = PluginInfoAsAUv2::new("aufx", "Tst0").inner;
+        assert!(!unsafe { get_auv2_info(std::ptr::null_mut(), 0, &mut info) });
+    }
+
+    #[test]
+    fn raw_get_auv2_info_catches_panics() {
+        let wrapper = PluginFactoryAsAUv2Wrapper::new(MANUFACTURER, PanickingFactory);
+
+        // A panic unwinding out of the extern "C" function would abort the test process.
+        let (found, info) = call_raw(&wrapper, 0);
+        assert!(!found);
+        assert_eq!(info.au_type, [0xff; 5]);
+        assert_eq!(info.au_subt, [0xff; 5]);
+
+        // The factory can still be called after a panic.
+        let (found, _) = call_raw(&wrapper, 1);
+        assert!(!found);
+    }
+}
@@ -1,58 +1,471 @@
 //! This module handles all CLAP callbacks that run on the audio thread.
 
+use crate::channel::Receiver;
+use crate::chunking::BlockChunker;
+use crate::delay::FeedbackDelay;
+use crate::denormals::FlushDenormals;
 use crate::main_thread::GainPluginMainThread;
+use crate::pan::pan_gains;
+use crate::params::{
+    report_feedback, report_gain, report_gesture_begin, report_gesture_end, report_mix, report_pan,
+};
+use crate::shared::GainShared;
+use crate::smoothing::LinearSmoother;
+use crate::tremolo::Tremolo;
+use clack_extensions::params::PluginAudioProcessorParams;
+use clack_extensions::render::RenderMode;
+use clack_extensions::tail::{PluginTailImpl, TailLength};
 use clack_plugin::prelude::*;
 
+/// Messages sent from the main thread to the audio processor,
+/// which forwards them to the host in the same order.
+#[derive(Copy, Clone, Debug)]
+pub enum GainMessage {
+    /// The main thread changed the gain, e.g. by loading a preset
+    /// or because the user moved a knob in a GUI,
+    /// which the host needs to be told about.
+    GainChanged(f32),
+
+    /// The main thread changed the pan, e.g. by loading a preset.
+    PanChanged(f32),
+
+    /// The main thread changed the delay's feedback, e.g. by loading a saved state.
+    FeedbackChanged(f32),
+
+    /// The main thread changed the delay's mix, e.g. by loading a saved state.
+    MixChanged(f32),
+
+    /// The user started changing the gain, e.g. by grabbing a knob in a GUI.
+    GestureBegin,
+
+    /// The user stopped changing the gain, e.g. by releasing a knob in a GUI.
+    GestureEnd,
+}
+
+/// How long it takes the applied gain to follow a change of the gain parameter, in seconds.
+const GAIN_RAMP_TIME: f64 = 0.01;
+
+/// The largest block size we accept from the host.
+/// Scratch buffers are allocated for the largest block, so a buggy host announcing
+/// billions of frames would otherwise make us allocate gigabytes.
+/// Hosts typically use at most a few thousand frames, even when rendering offline,
+/// and at this limit each scratch buffer takes 4 MB.
+const MAX_SUPPORTED_FRAMES_COUNT: u32 = 1 << 20;
+
 pub struct GainPluginProcessor<'a> {
-    #[allow(dead_code)] // unused in example
     host: HostAudioProcessorHandle<'a>,
 
-    /// The constant factor to multiply incoming samples with.
-    factor: f32,
+    /// The parameter values and host extensions, shared with the main thread.
+    shared: &'a GainShared,
+
+    /// The sample rate the host activated us with.
+    #[allow(dead_code)] // only used during activation in example
+    sample_rate: f64,
+
+    /// The maximum number of frames the host will pass to a single process call.
+    max_frames_count: u32,
+
+    /// Whether the host provides buffers for the output port.
+    /// This can't change while we're activated.
+    output_port_active: bool,
+
+    /// The channel counts of the main input and output port the host activated us with.
+    /// Per-channel state (e.g. meters) should be sized from these instead of assuming stereo.
+    input_channel_count: usize,
+    output_channel_count: usize,
+
+    /// Ramps the applied gain towards the gain parameter.
+    gain: LinearSmoother,
+
+    /// Ramps the applied pan towards the pan parameter.
+    pan: LinearSmoother,
+
+    /// Scratch space for the gain of every frame in the current block,
+    /// separately for the left and right channel, as the pan treats them differently.
+    gain_buffers: [Vec<f32>; 2],
+
+    /// Collects the signal into fixed-size chunks after the gain,
+    /// if the "chunked" feature is enabled.
+    chunker: Option<BlockChunker>,
+
+    /// The feedback delay applied after the gain, if the "delay" feature is enabled.
+    delay: Option<FeedbackDelay>,
+
+    /// The tempo-synced tremolo applied to the gain, if the "tremolo" feature is enabled.
+    tremolo: Option<Tremolo>,
+
+    /// Receives messages from the main thread.
+    messages: Receiver<GainMessage>,
+
+    /// A message from the main thread that couldn't be forwarded to the host yet,
+    /// because its output event queue was full.
+    /// Any later messages wait in the channel, so that their order is kept.
+    pending_message: Option<GainMessage>,
+
+    /// A gain value the host sent outside of the parameter's range,
+    /// whose corrected value the host hasn't been told about yet.
+    unreported_factor: Option<f32>,
+
+    /// Whether we're rendering in realtime or offline, as of the current block.
+    /// The gain is applied the same way in both modes.
+    #[allow(dead_code)] // unused in example
+    render_mode: RenderMode,
+}
+
+impl<'a> GainPluginProcessor<'a> {
+    /// The number of channels of the main input port.
+    #[allow(dead_code)] // unused in example
+    pub fn input_channel_count(&self) -> usize {
+        self.input_channel_count
+    }
+
+    /// The number of channels of the main output port.
+    #[allow(dead_code)] // unused in example
+    pub fn output_channel_count(&self) -> usize {
+        self.output_channel_count
+    }
+
+    /// Forwards all messages the main thread sent since the last call to the host.
+    /// If its output queue is full, we try again in the next call.
+    fn forward_messages(&mut self, output: &mut OutputEvents) {
+        while let Some(message) = self.pending_message.take().or_else(|| self.messages.try_recv()) {
+            let forwarded = match message {
+                // during a gesture, the gain may have changed several times since the last call.
+                // only the latest value needs to be reported.
+                GainMessage::GainChanged(factor) => {
+                    factor != self.shared.params.factor() || report_gain(output, factor).is_ok()
+                }
+                GainMessage::PanChanged(pan) => {
+                    pan != self.shared.params.pan() || report_pan(output, pan).is_ok()
+                }
+                GainMessage::FeedbackChanged(feedback) => {
+                    feedback != self.shared.params.feedback()
+                        || report_feedback(output, feedback).is_ok()
+                }
+                GainMessage::MixChanged(mix) => {
+                    mix != self.shared.params.mix() || report_mix(output, mix).is_ok()
+                }
+                GainMessage::GestureBegin => report_gesture_begin(output).is_ok(),
+                GainMessage::GestureEnd => report_gesture_end(output).is_ok(),
+            };
+
+            if !forwarded {
+                self.pending_message = Some(message);
+                return;
+            }
+        }
+    }
+
+    /// Tells the host about a corrected gain value, if any.
+    /// If its output queue is full, we try again in the next call.
+    fn report_gain_change(&mut self, output: &mut OutputEvents) {
+        // the host may have sent another value in the meantime,
+        // in which case there is nothing left to report.
+        let Some(factor) = self.unreported_factor.take() else {
+            return;
+        };
+        if factor == self.shared.params.factor() {
+            self.unreported_factor = report_gain(output, factor).err();
+        }
+    }
 }
 
-impl<'a> PluginAudioProcessor<'a, (), GainPluginMainThread<'a>> for GainPluginProcessor<'a> {
+impl<'a> PluginAudioProcessor<'a, GainShared, GainPluginMainThread<'a>> for GainPluginProcessor<'a> {
     fn activate(
         host: HostAudioProcessorHandle<'a>,
         main_thread: &mut GainPluginMainThread<'a>,
-        _shared: &'a (),
-        _audio_config: PluginAudioConfiguration,
+        shared: &'a GainShared,
+        audio_config: PluginAudioConfiguration,
     ) -> Result<Self, PluginError> {
-        // in a real plugin, you might set up
-        // communication lines with the main thread here.
+        // activation happens on the main thread.
+        shared.thread_checker.debug_assert_main_thread(&host.shared());
+
+        // reject block sizes we can't allocate buffers for before setting anything up.
+        // a host always processes at least one frame at a time.
+        if audio_config.max_frames_count == 0
+            || audio_config.min_frames_count > audio_config.max_frames_count
+        {
+            return Err(PluginError::Message("Host requested an invalid block size"));
+        }
+        if audio_config.max_frames_count > MAX_SUPPORTED_FRAMES_COUNT {
+            return Err(PluginError::Message("Host requested a block size that is too large"));
+        }
+
+        // set up communication lines with the main thread.
+        let messages = main_thread.connect_processor();
+
+        // anything depending on the sample rate or block size is set up here,
+        // as the host may activate us again with a different configuration.
+        let sample_rate = audio_config.sample_rate;
+
+        // the host can only change the port layout while we're deactivated,
+        // so the layout selected now holds until the next activation.
+        // this plugin's input and output port always share a layout.
+        let port_config = main_thread.port_config();
+        let input_channel_count = port_config.channel_count() as usize;
+        let output_channel_count = port_config.channel_count() as usize;
+
         Ok(Self {
             host,
-            factor: main_thread.factor,
+            shared,
+            sample_rate,
+            max_frames_count: audio_config.max_frames_count,
+            output_port_active: main_thread.output_port_active,
+            input_channel_count,
+            output_channel_count,
+            gain: LinearSmoother::new(shared.params.factor(), GAIN_RAMP_TIME, sample_rate),
+            pan: LinearSmoother::new(shared.params.pan(), GAIN_RAMP_TIME, sample_rate),
+            // allocating memory may block for an unbounded amount of time,
+            // which must never happen on the audio thread.
+            // so all buffers are allocated here, large enough for the largest block.
+            gain_buffers: [
+                vec![0.0; audio_config.max_frames_count as usize],
+                vec![0.0; audio_config.max_frames_count as usize],
+            ],
+            chunker: cfg!(feature = "chunked").then(|| BlockChunker::new(output_channel_count)),
+            delay: cfg!(feature = "delay")
+                .then(|| FeedbackDelay::new(output_channel_count, sample_rate, &shared.params)),
+            tremolo: cfg!(feature = "tremolo").then(|| Tremolo::new(sample_rate)),
+            messages,
+            pending_message: None,
+            unreported_factor: None,
+            render_mode: shared.render_mode.get(),
         })
     }
 
-    fn deactivate(self, _main_thread: &mut GainPluginMainThread<'a>) {
-        // here's where you tear down communications with the main thread.
+    fn deactivate(self, main_thread: &mut GainPluginMainThread<'a>) {
+        self.shared.thread_checker.debug_assert_main_thread(&self.host.shared());
+
+        // tear down communications with the main thread,
+        // which may now change the port layout.
+        main_thread.disconnect_processor();
+    }
+
+    fn reset(&mut self) {
+        // the host wants us to forget about all previously processed audio.
+        if let Some(chunker) = &mut self.chunker {
+            chunker.clear();
+        }
+        if let Some(delay) = &mut self.delay {
+            delay.clear();
+        }
     }
 
     /// This is where the DSP happens!
     /// This example plugin simply multiplies
-    /// the amplitude of the incoming signal with a constant factor.
+    /// the amplitude of the incoming signal with the (smoothed and modulated) gain parameter.
+    /// With the "tremolo" feature, the transport passed along with the audio
+    /// is used to make the gain pulse in time with the host's tempo.
+    ///
+    /// On a stereo port, the pan parameter additionally moves the signal between left and right.
+    /// Other layouts have no notion of left and right, so every channel is treated the same,
+    /// which makes this work for any channel count the host configured.
+    /// If input and output port have different channel counts,
+    /// surplus output channels are silenced and surplus input channels are ignored.
+    ///
+    /// CLAP output buffers are always replaced, never accumulated into:
+    /// they may contain garbage when handed to us, so every output sample must be written,
+    /// and hosts sum the outputs of several plugins themselves.
+    /// This differs from e.g. a JUCE `processBlock` that adds to a shared bus.
     fn process(
         &mut self,
-        _process: Process,
+        process: Process,
         mut audio: Audio,
-        _events: Events,
+        events: Events,
     ) -> Result<ProcessStatus, PluginError> {
+        self.shared.thread_checker.debug_assert_audio_thread(&self.host.shared());
+
+        // avoid CPU spikes on near-silent input until the end of the block.
+        let _flush_denormals = FlushDenormals::new();
+
+        // pick up a render mode the host set since the last block.
+        self.render_mode = self.shared.render_mode.get();
+
+        // for simplicity, parameter changes are applied at the start of the block.
+        // a sample-accurate plugin would split the block at each event
+        // using events.input.batch().
+        if let Some(corrected) = self.shared.params.handle_events(events.input) {
+            self.unreported_factor = Some(corrected);
+        }
+        self.forward_messages(events.output);
+        self.report_gain_change(events.output);
+
+        let frames_count = audio.frames_count();
+        debug_assert!(frames_count <= self.max_frames_count);
+
+        // compute the gain of every frame once, instead of once per channel.
+        self.gain.set_target(self.shared.params.modulated_factor());
+        self.pan.set_target(self.shared.params.pan());
+        let stereo = self.output_channel_count == 2;
+
+        let [left_buffer, right_buffer] = &mut self.gain_buffers;
+        let (Some(left_gains), Some(right_gains)) = (
+            left_buffer.get_mut(..frames_count as usize),
+            right_buffer.get_mut(..frames_count as usize),
+        ) else {
+            return Err(PluginError::Message("Host exceeded the maximum block size"));
+        };
+
+        // at a steady gain of exactly 1 (and a centered pan),
+        // the samples pass through unchanged, so multiplying them can be skipped altogether.
+        let unity = self.tremolo.is_none()
+            && self.gain.settled_value() == Some(1.0)
+            && (!stereo || self.pan.settled_value() == Some(0.0));
+
+        if !unity {
+            for (frame, gain) in left_gains.iter_mut().enumerate() {
+                *gain = self.gain.value_at(frame as u32);
+            }
+            if let Some(tremolo) = &mut self.tremolo {
+                tremolo.apply(process.transport, left_gains);
+            }
+
+            // both channels start out with the same gain, which the pan then scales per side.
+            right_gains.copy_from_slice(left_gains);
+            if stereo {
+                for (frame, (left, right)) in
+                    left_gains.iter_mut().zip(right_gains.iter_mut()).enumerate()
+                {
+                    let (left_pan, right_pan) = pan_gains(self.pan.value_at(frame as u32));
+                    *left *= left_pan;
+                    *right *= right_pan;
+                }
+            }
+        }
+        let (left_gains, right_gains) = (&*left_gains, &*right_gains);
+
+        if let Some(delay) = &mut self.delay {
+            delay.update(&self.shared.params);
+        }
+
         for mut port_pair in &mut audio {
+            // the host deactivated the output port, so there's nothing to write to.
+            if !self.output_port_active {
+                continue;
+            }
+
             let Some(channel_pairs) = port_pair.channels()?.into_f32() else {
                 continue;
             };
 
-            for pair in channel_pairs {
-                if let ChannelPair::InputOutput(input, output) = pair {
-                    for i in 0..input.len() {
-                        output[i] = input[i] * self.factor;
-                    }
+            for (channel, pair) in channel_pairs.into_iter().enumerate() {
+                // without a stereo layout, both buffers hold the same gains.
+                let gains = if channel == 1 { right_gains } else { left_gains };
+
+                // the chunks collected so far and a delay can still ring out on a silent output.
+                let Some(output) = apply_gain(pair, gains, unity) else {
+                    continue;
+                };
+
+                if let Some(chunker) = &mut self.chunker {
+                    chunker.process_channel(channel, output);
+                }
+                if let Some(delay) = &mut self.delay {
+                    delay.process_channel(channel, output);
                 }
             }
         }
 
+        self.gain.advance(frames_count);
+        self.pan.advance(frames_count);
+        if let Some(chunker) = &mut self.chunker {
+            chunker.advance(frames_count);
+        }
+        if let Some(delay) = &mut self.delay {
+            delay.advance(frames_count);
+        }
+
         Ok(ProcessStatus::ContinueIfNotQuiet)
     }
 }
+
+/// Multiplies each sample of a channel with the gain of its frame, writing to the output buffer.
+/// Returns the output buffer for any further processing, or `None` if there is no output.
+/// If `unity` is set, the gains are all 1 and the samples are copied unchanged.
+fn apply_gain<'b>(pair: ChannelPair<'b, f32>, gains: &[f32], unity: bool) -> Option<&'b mut [f32]> {
+    match pair {
+        // input and output buffers of a channel have the block's frame count.
+        // should a host disagree, only the frames both have are processed,
+        // and the rest of the output is silenced instead of keeping whatever it contained.
+        ChannelPair::InputOutput(input, output) => {
+            let len = input.len().min(output.len());
+            if unity {
+                output[..len].copy_from_slice(&input[..len]);
+                output[len..].fill(0.0);
+            } else {
+                let len = len.min(gains.len());
+                for ((output, input), gain) in output[..len].iter_mut().zip(input).zip(gains) {
+                    *output = input * gain;
+                }
+                output[len..].fill(0.0);
+            }
+            Some(output)
+        }
+        // the host may hand us the same buffer for input and output,
+        // in which case the samples have to be scaled in place.
+        ChannelPair::InPlace(buf) => {
+            if !unity {
+                for (sample, gain) in buf.iter_mut().zip(gains) {
+                    *sample *= gain;
+                }
+            }
+            Some(buf)
+        }
+        // an output without an input is silence (which must be written explicitly),
+        // e.g. because the host deactivated the input port.
+        ChannelPair::OutputOnly(output) => {
+            output.fill(0.0);
+            Some(output)
+        }
+        // an input without an output has nowhere to go.
+        ChannelPair::InputOnly(_) => None,
+    }
+}
+
+/// Called by the host to deliver parameter changes while the plugin is activated,
+/// but not processing (e.g. because the transport is stopped).
+impl<'a> PluginAudioProcessorParams for GainPluginProcessor<'a> {
+    fn flush(
+        &mut self,
+        input_parameter_changes: &InputEvents,
+        output_parameter_changes: &mut OutputEvents,
+    ) {
+        if let Some(corrected) = self.shared.params.handle_events(input_parameter_changes) {
+            self.unreported_factor = Some(corrected);
+        }
+        self.forward_messages(output_parameter_changes);
+        self.report_gain_change(output_parameter_changes);
+    }
+}
+
+/// Tells the host for how long the plugin keeps producing sound after the input went silent,
+/// so that it keeps processing until the last chunk was played back
+/// and the echoes of the delay have died down.
+impl<'a> PluginTailImpl for GainPluginProcessor<'a> {
+    fn get(&self) -> TailLength {
+        let chunk_tail = self.chunker.as_ref().map_or(0, BlockChunker::latency);
+        let delay_tail = self
+            .delay
+            .as_ref()
+            .map_or(0, |delay| delay.tail_length(&self.shared.params));
+        TailLength::Finite(chunk_tail + delay_tail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_beyond_the_input_is_silenced() {
+        let input = [1.0, -0.5];
+
+        let mut output = [f32::NAN; 4];
+        apply_gain(ChannelPair::InputOutput(&input, &mut output), &[2.0; 4], false);
+        assert_eq!(output, [2.0, -1.0, 0.0, 0.0]);
+
+        let mut output = [f32::NAN; 4];
+        apply_gain(ChannelPair::InputOutput(&input, &mut output), &[1.0; 4], true);
+        assert_eq!(output, [1.0, -0.5, 0.0, 0.0]);
+    }
+}
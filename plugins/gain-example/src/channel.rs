@@ -0,0 +1,44 @@
+//! This module provides a realtime-safe channel
+//! for passing messages between the main thread and the audio thread.
+//!
+//! It wraps a fixed-capacity single-producer single-consumer queue from the ringbuf crate.
+//! Sending and receiving never allocates, locks or blocks,
+//! so either half can safely be used on the audio thread.
+//!
+//! As the audio processor only exists while the plugin is activated,
+//! a channel is typically created in `activate`:
+//! the processor owns the half it uses (e.g. the receiver for messages from the main thread),
+//! while the main thread keeps the other half in an `Option` until `deactivate`.
+//! Keeping a half in the plugin's shared state instead would require a lock,
+//! as only one thread at a time may use each half.
+
+use ringbuf::traits::{Consumer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+
+/// Creates a channel that can hold up to `capacity` messages that haven't been received yet.
+/// This allocates, so it must not be called on the audio thread.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let (producer, consumer) = HeapRb::new(capacity).split();
+    (Sender(producer), Receiver(consumer))
+}
+
+/// The sending half of a [`channel`].
+pub struct Sender<T>(HeapProd<T>);
+
+impl<T> Sender<T> {
+    /// Sends a message without blocking.
+    /// If the channel is full, the message is handed back.
+    pub fn send(&mut self, message: T) -> Result<(), T> {
+        self.0.try_push(message)
+    }
+}
+
+/// The receiving half of a [`channel`].
+pub struct Receiver<T>(HeapCons<T>);
+
+impl<T> Receiver<T> {
+    /// Receives the oldest message without blocking, if there is one.
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.0.try_pop()
+    }
+}
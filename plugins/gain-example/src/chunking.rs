@@ -0,0 +1,76 @@
+//! This module processes audio in chunks of a fixed size,
+//! independent of how many frames the host passes to each process call,
+//! if the "chunked" feature is enabled.
+//!
+//! Some DSP, e.g. FFT-based effects or convolution, can only work on blocks of a fixed size.
+//! Incoming samples are collected until a full chunk is available,
+//! while the output plays back the previously processed chunk.
+//! The output therefore lags behind the input by one chunk,
+//! which is reported to the host through the latency extension so that it can compensate.
+
+/// The number of frames in each chunk, which is also the latency this adds.
+pub const CHUNK_SIZE: usize = 512;
+
+pub struct BlockChunker {
+    /// A buffer per channel, holding one chunk.
+    /// Before the current position, it holds the input collected for the next chunk.
+    /// From the current position on, it holds the processed chunk that is still being played back.
+    buffers: Vec<Vec<f32>>,
+
+    /// The position in the buffers where the current block starts.
+    position: usize,
+}
+
+impl BlockChunker {
+    /// Allocates the buffers for the given channel count.
+    /// This must not be called on the audio thread.
+    pub fn new(channel_count: usize) -> Self {
+        Self {
+            buffers: vec![vec![0.0; CHUNK_SIZE]; channel_count],
+            position: 0,
+        }
+    }
+
+    /// Feeds a channel of the current block into the chunks, in place:
+    /// each sample is replaced by the processed sample from one chunk earlier.
+    pub fn process_channel(&mut self, channel: usize, samples: &mut [f32]) {
+        let Some(buffer) = self.buffers.get_mut(channel) else {
+            return;
+        };
+
+        let mut position = self.position;
+        for sample in samples {
+            std::mem::swap(sample, &mut buffer[position]);
+
+            position += 1;
+            if position == CHUNK_SIZE {
+                // the buffer is now filled with fresh input,
+                // which is processed and played back while the next chunk is collected.
+                process_chunk(buffer);
+                position = 0;
+            }
+        }
+    }
+
+    /// Moves on to the next block, after the current one with the given number of frames.
+    pub fn advance(&mut self, frames: u32) {
+        self.position = (self.position + frames as usize) % CHUNK_SIZE;
+    }
+
+    /// Discards all collected and processed samples, e.g. when the host resets the plugin.
+    pub fn clear(&mut self) {
+        for buffer in &mut self.buffers {
+            buffer.fill(0.0);
+        }
+    }
+
+    /// How many frames the output lags behind the input.
+    pub fn latency(&self) -> u32 {
+        CHUNK_SIZE as u32
+    }
+}
+
+/// Processes a full chunk of a single channel in place.
+/// This is where e.g. an FFT-based effect would transform the chunk and back.
+/// The gain has already been applied sample by sample, so the chunk is passed through unchanged.
+fn process_chunk(_chunk: &mut [f32]) {}
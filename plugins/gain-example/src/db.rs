@@ -0,0 +1,98 @@
+//! This module converts between decibels and the linear factors samples are multiplied with.
+//!
+//! Gains are applied as linear factors, but users think in decibels,
+//! so both the main thread (for displaying and parsing values) and the audio thread
+//! should convert through these functions instead of repeating the formula.
+//! Note that amplitudes use `20 * log10`, not `10 * log10` (which is for power),
+//! so doubling the amplitude is roughly +6 dB.
+
+/// Converts a gain in decibels to a linear factor.
+/// -inf dB is silence, i.e. a factor of 0.
+pub fn db_to_linear(db: f32) -> f32 {
+    if db == f32::NEG_INFINITY {
+        return 0.0;
+    }
+    10f32.powf(db / 20.0)
+}
+
+/// Converts a linear factor to a gain in decibels.
+/// A factor of 0 (or below, which has no meaningful level) is -inf dB.
+pub fn linear_to_db(factor: f32) -> f32 {
+    if factor <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    20.0 * factor.log10()
+}
+
+/// Parses a gain entered by the user into a linear factor.
+/// Accepts a level like "-6 dB", "+6 dB" or "-inf dB",
+/// as well as a factor like "0.5" or the "0.50x (-6.0 dB)" the gain parameter is displayed as.
+pub fn parse_gain(text: &str) -> Option<f32> {
+    let lowercase = text.trim().to_ascii_lowercase();
+    if let Some(db) = lowercase
+        .strip_suffix("db")
+        .filter(|_| !lowercase.contains('x'))
+    {
+        let db: f32 = db.trim().parse().ok()?;
+        return Some(db_to_linear(db));
+    }
+
+    let factor = lowercase
+        .split_once('x')
+        .map_or(&*lowercase, |(factor, _)| factor);
+    factor.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn unity_is_zero_db() {
+        assert_close(linear_to_db(1.0), 0.0);
+        assert_close(db_to_linear(0.0), 1.0);
+    }
+
+    #[test]
+    fn silence_is_negative_infinity() {
+        assert_eq!(linear_to_db(0.0), f32::NEG_INFINITY);
+        assert_eq!(linear_to_db(-1.0), f32::NEG_INFINITY);
+        assert_eq!(db_to_linear(f32::NEG_INFINITY), 0.0);
+    }
+
+    #[test]
+    fn doubling_is_six_db() {
+        assert_close(linear_to_db(2.0), 6.0206);
+        assert_close(db_to_linear(6.0206), 2.0);
+        assert_close(linear_to_db(0.5), -6.0206);
+    }
+
+    #[test]
+    fn parses_levels() {
+        assert_close(parse_gain("+6 dB").unwrap(), 1.9953);
+        assert_close(parse_gain("-6dB").unwrap(), 0.5012);
+        assert_close(parse_gain(" 0 DB ").unwrap(), 1.0);
+        assert_eq!(parse_gain("-inf dB"), Some(0.0));
+    }
+
+    #[test]
+    fn parses_factors() {
+        assert_eq!(parse_gain("0.5"), Some(0.5));
+        assert_eq!(parse_gain("2x"), Some(2.0));
+        assert_eq!(parse_gain("0.50x (-6.0 dB)"), Some(0.5));
+    }
+
+    #[test]
+    fn rejects_other_text() {
+        assert_eq!(parse_gain(""), None);
+        assert_eq!(parse_gain("dB"), None);
+        assert_eq!(parse_gain("loud"), None);
+    }
+}
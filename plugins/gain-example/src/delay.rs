@@ -0,0 +1,177 @@
+//! This module implements a simple feedback delay,
+//! which is applied after the gain if the "delay" feature is enabled.
+//!
+//! Unlike the gain, a delay depends on past samples,
+//! so it needs memory that is allocated when the plugin is activated,
+//! and it keeps producing sound after the input went silent (its "tail").
+//!
+//! The delay time, feedback and mix are read from the plugin's parameters every block,
+//! so the memory is allocated for the longest delay time.
+
+use crate::params::{GainParams, MAX_DELAY_TIME};
+
+/// The level below which an echo is considered inaudible (-60 dB).
+const SILENCE_THRESHOLD: f32 = 0.001;
+
+pub struct FeedbackDelay {
+    /// A circular buffer per channel, holding the samples of the longest delay time.
+    buffers: Vec<Vec<f32>>,
+
+    /// The position in the buffers where the current block starts.
+    position: usize,
+
+    /// The sample rate the buffers were allocated for.
+    sample_rate: f64,
+
+    /// The delay time of the current block, in samples.
+    delay_samples: usize,
+
+    /// The feedback of the current block.
+    feedback: f32,
+
+    /// The wet/dry mix of the current block.
+    mix: f32,
+}
+
+impl FeedbackDelay {
+    /// Allocates the buffers for the given channel count and sample rate.
+    /// This must not be called on the audio thread.
+    pub fn new(channel_count: usize, sample_rate: f64, params: &GainParams) -> Self {
+        let max_delay_samples = ((MAX_DELAY_TIME as f64 * sample_rate) as usize).max(1);
+
+        let mut delay = Self {
+            buffers: vec![vec![0.0; max_delay_samples]; channel_count],
+            position: 0,
+            sample_rate,
+            delay_samples: 1,
+            feedback: 0.0,
+            mix: 0.0,
+        };
+        delay.update(params);
+        delay
+    }
+
+    /// Picks up the current parameter values, before processing a block.
+    /// All channels of a block are processed with the same values.
+    pub fn update(&mut self, params: &GainParams) {
+        self.delay_samples = self.delay_samples(params.delay_time());
+        self.feedback = params.feedback();
+        self.mix = params.mix();
+    }
+
+    /// The given delay time in samples, limited to the length of the buffers.
+    fn delay_samples(&self, delay_time: f32) -> usize {
+        let max_delay_samples = self.buffers.first().map_or(1, Vec::len);
+        ((delay_time as f64 * self.sample_rate) as usize).clamp(1, max_delay_samples)
+    }
+
+    /// Mixes the echoes into a channel of the current block, in place.
+    /// The dry signal isn't delayed, so the delay adds no latency.
+    pub fn process_channel(&mut self, channel: usize, samples: &mut [f32]) {
+        let Some(buffer) = self.buffers.get_mut(channel) else {
+            return;
+        };
+        let len = buffer.len();
+
+        let mut position = self.position;
+        for sample in samples {
+            // the delay time is at most the buffer length,
+            // in which case the oldest sample is read right before it is overwritten.
+            let delayed = buffer[(position + len - self.delay_samples) % len];
+            buffer[position] = *sample + delayed * self.feedback;
+            *sample = *sample * (1.0 - self.mix) + delayed * self.mix;
+
+            position = (position + 1) % len;
+        }
+    }
+
+    /// Moves on to the next block, after the current one with the given number of frames.
+    pub fn advance(&mut self, frames: u32) {
+        if let Some(len) = self.buffers.first().map(Vec::len) {
+            self.position = (self.position + frames as usize) % len;
+        }
+    }
+
+    /// Silences all echoes, e.g. when the host resets the plugin.
+    pub fn clear(&mut self) {
+        for buffer in &mut self.buffers {
+            buffer.fill(0.0);
+        }
+    }
+
+    /// How many frames it takes the echoes to become inaudible after the input went silent,
+    /// with the current parameter values.
+    pub fn tail_length(&self, params: &GainParams) -> u32 {
+        let mix = params.mix();
+        if mix <= SILENCE_THRESHOLD {
+            return 0;
+        }
+
+        // the first echo is mixed in at `mix`, and each further one is `feedback` times as loud,
+        // so we count the echoes until they drop below the threshold.
+        // without feedback, the logarithm is -inf, which leaves just the first echo.
+        let further_echoes = (SILENCE_THRESHOLD / mix).ln() / params.feedback().ln();
+        let echoes = 1 + further_echoes.floor() as usize;
+        (echoes * self.delay_samples(params.delay_time())) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sample rate at which the delay time is a whole number of samples.
+    const SAMPLE_RATE: f64 = 1000.0;
+
+    fn params(delay_time: f32, feedback: f32, mix: f32) -> GainParams {
+        let params = GainParams::new(1.0);
+        params.set_delay_time(delay_time);
+        params.set_feedback(feedback);
+        params.set_mix(mix);
+        params
+    }
+
+    #[test]
+    fn echoes_follow_the_parameters() {
+        let mut delay = FeedbackDelay::new(1, SAMPLE_RATE, &params(0.5, 0.5, 0.5));
+
+        let mut samples = vec![0.0; 1001];
+        samples[0] = 1.0;
+        delay.process_channel(0, &mut samples);
+
+        // the dry impulse, then an echo every 500 samples, each half as loud as the previous one.
+        assert_eq!(samples[0], 0.5);
+        assert_eq!(samples[500], 0.5);
+        assert_eq!(samples[1000], 0.25);
+        assert_eq!(samples.iter().filter(|sample| **sample != 0.0).count(), 3);
+    }
+
+    #[test]
+    fn delay_time_changes_apply_to_the_next_block() {
+        let mut delay = FeedbackDelay::new(1, SAMPLE_RATE, &params(0.5, 0.0, 1.0));
+
+        let mut samples = vec![0.0; 100];
+        samples[0] = 1.0;
+        delay.process_channel(0, &mut samples);
+        delay.advance(100);
+
+        // the impulse is now 100 samples old, so it is due right away.
+        delay.update(&params(0.1, 0.0, 1.0));
+        let mut samples = vec![0.0; 100];
+        delay.process_channel(0, &mut samples);
+        assert_eq!(samples[0], 1.0);
+    }
+
+    #[test]
+    fn tail_length_follows_the_parameters() {
+        let delay = FeedbackDelay::new(1, SAMPLE_RATE, &params(0.5, 0.5, 0.5));
+
+        // a single echo without feedback, and none without the echoes being mixed in.
+        assert_eq!(delay.tail_length(&params(0.5, 0.0, 0.5)), 500);
+        assert_eq!(delay.tail_length(&params(0.5, 0.5, 0.0)), 0);
+
+        // the 10th echo is at 0.5^10 < 0.001 (-60 dB).
+        assert_eq!(delay.tail_length(&params(0.5, 0.5, 0.5)), 9 * 500);
+        assert_eq!(delay.tail_length(&params(1.0, 0.5, 0.5)), 9 * 1000);
+    }
+}
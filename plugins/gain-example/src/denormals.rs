@@ -0,0 +1,96 @@
+//! This module prevents the CPU from processing denormal numbers on the audio thread.
+//!
+//! Floats extremely close to zero, e.g. in the decaying tail of a delay,
+//! are stored in a special "denormal" format that many CPUs process up to 100 times slower.
+//! This easily causes CPU spikes once the input falls silent.
+//! As such tiny values are inaudible anyway, the CPU is told to treat them as zero
+//! while we're processing, and the host's settings are restored afterwards.
+
+/// While this guard exists, denormals are flushed to zero on the current thread.
+/// The previous floating point settings are restored when it is dropped,
+/// as the host may rely on them for its own processing.
+pub struct FlushDenormals {
+    /// The value of the floating point control register before it was changed.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    previous: arch::ControlRegister,
+}
+
+impl FlushDenormals {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    pub fn new() -> Self {
+        let previous = arch::read_control_register();
+        arch::write_control_register(previous | arch::FLUSH_DENORMALS);
+        Self { previous }
+    }
+
+    /// Other architectures aren't covered by this example.
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Drop for FlushDenormals {
+    fn drop(&mut self) {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+        arch::write_control_register(self.previous);
+    }
+}
+
+/// On x86, SSE instructions are controlled by the MXCSR register.
+/// The `_mm_getcsr` and `_mm_setcsr` intrinsics are deprecated,
+/// so the register is accessed with the respective instructions directly.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod arch {
+    use std::arch::asm;
+
+    pub type ControlRegister = u32;
+
+    /// The "flush to zero" (FTZ) and "denormals are zero" (DAZ) bits,
+    /// which make SSE instructions output and read denormals as zero.
+    pub const FLUSH_DENORMALS: ControlRegister = 0x8040;
+
+    pub fn read_control_register() -> ControlRegister {
+        let mut mxcsr = 0;
+        // SAFETY: stmxcsr only stores the register to the given, valid location.
+        unsafe {
+            asm!("stmxcsr [{}]", in(reg) &mut mxcsr, options(nostack, preserves_flags));
+        }
+        mxcsr
+    }
+
+    pub fn write_control_register(mxcsr: ControlRegister) {
+        // SAFETY: ldmxcsr only changes how floating point operations behave on this thread.
+        unsafe {
+            asm!("ldmxcsr [{}]", in(reg) &mxcsr, options(nostack, readonly, preserves_flags));
+        }
+    }
+}
+
+/// On ARM, e.g. Apple Silicon, floating point operations are controlled by the FPCR register.
+#[cfg(target_arch = "aarch64")]
+mod arch {
+    use std::arch::asm;
+
+    pub type ControlRegister = u64;
+
+    /// The "flush to zero" (FZ) bit, which makes floating point instructions
+    /// output and read denormals as zero.
+    pub const FLUSH_DENORMALS: ControlRegister = 1 << 24;
+
+    pub fn read_control_register() -> ControlRegister {
+        let fpcr;
+        // SAFETY: reading FPCR has no side effects.
+        unsafe {
+            asm!("mrs {}, fpcr", out(reg) fpcr, options(nomem, nostack, preserves_flags));
+        }
+        fpcr
+    }
+
+    pub fn write_control_register(fpcr: ControlRegister) {
+        // SAFETY: writing FPCR only changes how floating point operations behave on this thread.
+        unsafe {
+            asm!("msr fpcr, {}", in(reg) fpcr, options(nomem, nostack, preserves_flags));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! This module describes what kind of plugin we're exposing in a single place,
+//! and derives the metadata of each plugin format from it,
+//! so that e.g. the CLAP features and the AU type can't drift apart.
+//!
+//! VST3 subcategories don't need to be set explicitly:
+//! clap-wrapper derives them from the CLAP features if none are given.
+
+use clack_plugin::plugin::features::{AUDIO_EFFECT, INSTRUMENT, NOTE_EFFECT};
+use std::ffi::CStr;
+
+/// The main kind of a plugin, which every plugin format expresses differently.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[allow(dead_code)] // not all kinds are used in example
+pub enum PluginKind {
+    /// Processes incoming audio.
+    AudioEffect,
+    /// Generates audio from incoming notes.
+    Instrument,
+    /// Processes incoming audio depending on incoming notes.
+    NoteEffect,
+}
+
+impl PluginKind {
+    fn clap_feature(self) -> &'static CStr {
+        match self {
+            PluginKind::AudioEffect => AUDIO_EFFECT,
+            PluginKind::Instrument => INSTRUMENT,
+            PluginKind::NoteEffect => NOTE_EFFECT,
+        }
+    }
+
+    /// The 4-char AU component type.
+    fn au_type(self) -> &'static str {
+        match self {
+            PluginKind::AudioEffect => "aufx",
+            PluginKind::Instrument => "aumu",
+            PluginKind::NoteEffect => "aumf",
+        }
+    }
+}
+
+/// The kind of a plugin, plus any additional CLAP features
+/// (e.g. `STEREO`, `MIXING`, `DISTORTION`) that further categorize it.
+#[derive(Clone, Debug)]
+pub struct PluginFeatures {
+    kind: PluginKind,
+    features: Vec<&'static CStr>,
+}
+
+impl PluginFeatures {
+    pub fn new(kind: PluginKind) -> Self {
+        Self {
+            kind,
+            features: Vec::new(),
+        }
+    }
+
+    /// Adds a CLAP feature from `clack_plugin::plugin::features`.
+    pub fn with(mut self, feature: &'static CStr) -> Self {
+        self.features.push(feature);
+        self
+    }
+
+    /// All CLAP features, starting with the one for the plugin kind.
+    pub fn clap_features(&self) -> impl Iterator<Item = &'static CStr> + '_ {
+        std::iter::once(self.kind.clap_feature()).chain(self.features.iter().copied())
+    }
+
+    /// The AU component type matching the plugin kind.
+    pub fn au_type(&self) -> &'static str {
+        self.kind.au_type()
+    }
+}
@@ -0,0 +1,38 @@
+//! This module forwards diagnostic messages to the host's log extension,
+//! so they end up in the host's log window or console
+//! instead of disappearing when debugging inside a DAW.
+//!
+//! Messages are sent through the plugin instance's own host handle,
+//! so each one ends up in the log of the host that created the instance.
+//! If the host doesn't implement the log extension, messages are printed to stderr
+//! in debug builds only: a plugin is loaded into someone else's process,
+//! whose output isn't ours to write to.
+
+use clack_extensions::log::{HostLog, LogSeverity};
+use clack_plugin::prelude::*;
+use std::ffi::CStr;
+
+/// Sends log messages to the host.
+#[derive(Copy, Clone)]
+pub struct HostLogger(Option<HostLog>);
+
+impl HostLogger {
+    pub fn new(host: &HostSharedHandle) -> Self {
+        Self(host.get_extension())
+    }
+
+    /// Logs a message with the given severity.
+    ///
+    /// Hosts are allowed to do blocking work when receiving a log message,
+    /// so only call this from the main thread.
+    pub fn log(&self, host: &HostSharedHandle, severity: LogSeverity, message: &CStr) {
+        match &self.0 {
+            Some(log) => log.log(host, severity, message),
+            None => {
+                if cfg!(debug_assertions) {
+                    eprintln!("[{severity:?}] {}", message.to_string_lossy());
+                }
+            }
+        }
+    }
+}
@@ -2,20 +2,55 @@
 //! that is exposed behind the CLAP entry points.
 
 mod audio_thread;
+mod channel;
+mod chunking;
+mod db;
+mod delay;
+mod denormals;
+mod features;
+mod host_log;
 mod main_thread;
+mod output_events;
+mod pan;
+mod params;
+mod presets;
+mod shared;
+mod smoothing;
+mod state;
+#[cfg(test)]
+mod test_host;
+mod thread_check;
+mod tremolo;
 
 use crate::audio_thread::GainPluginProcessor;
+use crate::features::{PluginFeatures, PluginKind};
 use crate::main_thread::GainPluginMainThread;
+use crate::presets::GainPresetDiscoveryFactory;
+use crate::shared::GainShared;
 use clack_extensions::audio_ports::PluginAudioPorts;
+use clack_extensions::audio_ports_activation::PluginAudioPortsActivation;
+use clack_extensions::audio_ports_config::PluginAudioPortsConfig;
+use clack_extensions::latency::PluginLatency;
+use clack_extensions::param_indication::PluginParamIndication;
+use clack_extensions::params::PluginParams;
+use clack_extensions::preset_discovery::PresetDiscoveryFactoryWrapper;
+use clack_extensions::preset_load::PluginPresetLoad;
+use clack_extensions::remote_controls::PluginRemoteControls;
+use clack_extensions::render::PluginRender;
+use clack_extensions::state::PluginState;
+use clack_extensions::state_context::PluginStateContext;
+use clack_extensions::tail::PluginTail;
+use clack_extensions::track_info::PluginTrackInfo;
+use clack_extensions::voice_info::PluginVoiceInfo;
 use clack_plugin::clack_entry;
 use clack_plugin::entry::prelude::*;
-use clack_plugin::plugin::features::AUDIO_EFFECT;
+use clack_plugin::plugin::features::UTILITY;
 use clack_plugin::prelude::*;
 use clap_wrapper_extensions::auv2::{
-    PluginFactoryAsAUv2, PluginFactoryAsAUv2Wrapper, PluginInfoAsAUv2,
+    AUv2Manufacturer, PluginFactoryAsAUv2, PluginFactoryAsAUv2Wrapper, PluginInfoAsAUv2,
 };
 use clap_wrapper_extensions::vst3::{PluginFactoryAsVST3, PluginInfoAsVST3};
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 
 pub struct GainPlugin;
 
@@ -23,38 +58,74 @@ impl Plugin for GainPlugin {
     type AudioProcessor<'a> = GainPluginProcessor<'a>;
     type MainThread<'a> = GainPluginMainThread<'a>;
 
-    /// We don't use any shared state in this example.
+    /// The state shared between threads: the parameter values,
+    /// as well as data that never changes after the plugin instance was created.
     ///
     /// Generally, it is preferred in Rust to communicate data between threads
     /// by passing messages through queues instead of sharing state.
-    /// You can use the ringbuf crate or any other lock-free realtime-safe
-    /// queue to achieve this in practice.
-    type Shared<'a> = ();
+    /// See the shared module for when sharing state is appropriate,
+    /// and the channel module for a lock-free realtime-safe queue.
+    type Shared<'a> = GainShared;
 
     fn declare_extensions(
         builder: &mut PluginExtensions<Self>,
         _shared: Option<&Self::Shared<'_>>,
     ) {
-        builder.register::<PluginAudioPorts>();
+        builder
+            .register::<PluginAudioPorts>()
+            .register::<PluginAudioPortsActivation>()
+            .register::<PluginAudioPortsConfig>()
+            .register::<PluginLatency>()
+            .register::<PluginParamIndication>()
+            .register::<PluginParams>()
+            .register::<PluginPresetLoad>()
+            .register::<PluginRemoteControls>()
+            .register::<PluginRender>()
+            .register::<PluginState>()
+            .register::<PluginStateContext>()
+            .register::<PluginTail>()
+            .register::<PluginTrackInfo>()
+            .register::<PluginVoiceInfo>();
     }
 }
 
-/// Contains the CLAP, VST3 and AUv2 descriptors for a single plugin.
+/// Contains the CLAP, VST3 and AUv2 descriptors for a single plugin,
+/// as well as the default gain factor of the plugin.
 struct PluginInfo(
     PluginDescriptor,
     PluginInfoAsVST3<'static>,
     PluginInfoAsAUv2,
+    f32,
 );
 
+impl PluginInfo {
+    fn new(
+        id: &str,
+        name: &str,
+        features: &PluginFeatures,
+        au_subtype: &str,
+        factor: f32,
+    ) -> Self {
+        Self(
+            PluginDescriptor::new(id, name).with_features(features.clap_features()),
+            // clap-wrapper derives the VST3 subcategories from the CLAP features
+            PluginInfoAsVST3::new(Some(&VST3_VENDOR), None, None),
+            PluginInfoAsAUv2::new(features.au_type(), au_subtype),
+            factor,
+        )
+    }
+}
+
 /// The factory exposes the plugins that can be instantiated from this binary.
 pub struct GainPluginFactory {
-    info_halver: PluginInfo,
-    info_doubler: PluginInfo,
+    plugins: Vec<PluginInfo>,
 }
 
 const VST3_VENDOR: &CStr = c"free-audio";
-const AU_MANUFACTURER_CODE: &CStr = c"Frau";
-const AU_MANUFACTURER_NAME: &CStr = c"free-audio";
+
+// all AU plugins in this binary are advertised under the same manufacturer.
+// to ship plugins under another brand, build them from a separate crate with its own manufacturer.
+const AU_MANUFACTURER: AUv2Manufacturer = AUv2Manufacturer::new(c"Frau", c"free-audio");
 
 // 4-char IDs for the AU descriptors
 const AU_ID_HALVER: &str = "Ghlv";
@@ -62,37 +133,65 @@ const AU_ID_DOUBLER: &str = "Gdbl";
 
 impl GainPluginFactory {
     fn new() -> Self {
-        Self {
-            info_halver: PluginInfo(
-                PluginDescriptor::new("free-audio.clap.rust-gain-example.halver", "Gain Halver")
-                    .with_features([AUDIO_EFFECT]),
-                PluginInfoAsVST3::new(Some(&VST3_VENDOR), None, None),
-                PluginInfoAsAUv2::new("aufx", AU_ID_HALVER),
+        // the categories of all plugins are described once,
+        // and the metadata for each plugin format is derived from them.
+        let features = PluginFeatures::new(PluginKind::AudioEffect).with(UTILITY);
+
+        // to expose another variation of the gain plugin,
+        // simply add another entry to this list.
+        let plugins = vec![
+            PluginInfo::new(
+                "free-audio.clap.rust-gain-example.halver",
+                "Gain Halver",
+                &features,
+                AU_ID_HALVER,
+                0.5,
             ),
-            info_doubler: PluginInfo(
-                PluginDescriptor::new("free-audio.clap.rust-gain-example.doubler", "Gain Doubler")
-                    .with_features([AUDIO_EFFECT]),
-                PluginInfoAsVST3::new(Some(&VST3_VENDOR), None, None),
-                PluginInfoAsAUv2::new("aufx", AU_ID_DOUBLER),
+            PluginInfo::new(
+                "free-audio.clap.rust-gain-example.doubler",
+                "Gain Doubler",
+                &features,
+                AU_ID_DOUBLER,
+                2.0,
             ),
+        ];
+
+        // AU hosts identify plugins by their subtype,
+        // so reusing one makes them load the wrong plugin.
+        for (index, info) in plugins.iter().enumerate() {
+            debug_assert!(
+                plugins[..index]
+                    .iter()
+                    .all(|other| other.2.au_subtype() != info.2.au_subtype()),
+                "AU subtype {:?} is used by more than one plugin",
+                String::from_utf8_lossy(info.2.au_subtype())
+            );
         }
+
+        Self { plugins }
+    }
+
+    /// The plugin with the given CLAP id, if this factory exposes it.
+    fn find_plugin(&self, plugin_id: &CStr) -> Option<&PluginInfo> {
+        self.plugins.iter().find(|info| info.0.id() == plugin_id)
+    }
+
+    /// The CLAP ids of all plugins exposed by this factory.
+    fn plugin_ids(&self) -> Vec<CString> {
+        self.plugins.iter().map(|info| info.0.id().to_owned()).collect()
     }
 }
 
-/// Implements a plugin factory that exposes 2 plugins.
+/// Implements a plugin factory that exposes all plugins in the list.
 /// For this gain example, one plugin halves the incoming audio,
 /// and the other doubles incoming audio.
 impl PluginFactory for GainPluginFactory {
     fn plugin_count(&self) -> u32 {
-        2
+        self.plugins.len() as u32
     }
 
     fn plugin_descriptor(&self, index: u32) -> Option<&PluginDescriptor> {
-        match index {
-            0 => Some(&self.info_halver.0),
-            1 => Some(&self.info_doubler.0),
-            _ => None,
-        }
+        self.plugins.get(index as usize).map(|info| &info.0)
     }
 
     fn create_plugin<'b>(
@@ -100,71 +199,61 @@ impl PluginFactory for GainPluginFactory {
         host_info: HostInfo<'b>,
         plugin_id: &CStr,
     ) -> Option<PluginInstance<'b>> {
-        // the only way in which the two exposed plugins differ
-        // is the gain factor that is passed to the main thread upon creation.
-
-        if plugin_id == self.info_halver.0.id() {
-            Some(PluginInstance::new::<GainPlugin>(
-                host_info,
-                &self.info_halver.0,
-                |_host| Ok(()),
-                |host, _| GainPluginMainThread::create(host, 0.5),
-            ))
-        } else if plugin_id == self.info_doubler.0.id() {
-            Some(PluginInstance::new::<GainPlugin>(
-                host_info,
-                &self.info_doubler.0,
-                |_host| Ok(()),
-                |host, _| GainPluginMainThread::create(host, 2.0),
-            ))
-        } else {
-            None
-        }
+        // the only way in which the exposed plugins differ
+        // is the default value of their gain parameter.
+        let info = self.find_plugin(plugin_id)?;
+        let factor = info.3;
+
+        Some(PluginInstance::new::<GainPlugin>(
+            host_info,
+            &info.0,
+            move |host| Ok(GainShared::new(host, factor)),
+            |host, shared| GainPluginMainThread::create(host, shared),
+        ))
     }
 }
 
 impl PluginFactoryAsVST3 for GainPluginFactory {
     fn get_vst3_info(&self, index: u32) -> Option<&PluginInfoAsVST3> {
-        match index {
-            0 => Some(&self.info_halver.1),
-            1 => Some(&self.info_doubler.1),
-            _ => None,
-        }
+        self.plugins.get(index as usize).map(|info| &info.1)
     }
 }
 
 impl PluginFactoryAsAUv2 for GainPluginFactory {
     fn get_auv2_info(&self, index: u32) -> Option<PluginInfoAsAUv2> {
-        match index {
-            0 => Some(self.info_halver.2),
-            1 => Some(self.info_doubler.2),
-            _ => None,
-        }
+        self.plugins.get(index as usize).map(|info| info.2)
     }
 }
 
-/// Provides the CLAP entry points by deferring to our factory.
+/// Provides the CLAP entry points by deferring to our factories.
 pub struct GainPluginEntry {
     factory: PluginFactoryWrapper<GainPluginFactory>,
     factory_auv2: PluginFactoryAsAUv2Wrapper<GainPluginFactory>,
+    factory_presets: PresetDiscoveryFactoryWrapper<GainPresetDiscoveryFactory>,
 }
 
 impl Entry for GainPluginEntry {
     fn new(_bundle_path: &CStr) -> Result<Self, EntryLoadError> {
+        let plugin_factory = GainPluginFactory::new();
+
+        // the factory presets can be loaded into any of our plugins.
+        let preset_factory = GainPresetDiscoveryFactory::new(plugin_factory.plugin_ids());
+
         Ok(Self {
-            factory: PluginFactoryWrapper::new(GainPluginFactory::new()),
+            factory: PluginFactoryWrapper::new(plugin_factory),
             factory_auv2: PluginFactoryAsAUv2Wrapper::new(
-                AU_MANUFACTURER_CODE,
-                AU_MANUFACTURER_NAME,
+                AU_MANUFACTURER,
                 GainPluginFactory::new(),
             ),
+            factory_presets: PresetDiscoveryFactoryWrapper::new(preset_factory),
         })
     }
 
     fn declare_factories<'a>(&'a self, builder: &mut EntryFactories<'a>) {
         builder
             .register_factory(&self.factory)
-            .register_factory(&self.factory_auv2);
+            .register_factory(&self.factory_auv2)
+            .register_factory(&self.factory_presets);
     }
 }
 
@@ -180,3 +269,64 @@ impl Entry for GainPluginEntry {
 #[allow(warnings, unused)]
 #[unsafe(no_mangle)]
 pub static rust_clap_entry: EntryDescriptor = clack_entry!(GainPluginEntry);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ids of the exposed plugins, along with the gain factor each one defaults to.
+    const PLUGINS: [(&CStr, f32); 2] = [
+        (c"free-audio.clap.rust-gain-example.halver", 0.5),
+        (c"free-audio.clap.rust-gain-example.doubler", 2.0),
+    ];
+
+    #[test]
+    fn exposes_a_descriptor_per_plugin() {
+        let factory = GainPluginFactory::new();
+        assert_eq!(factory.plugin_count(), PLUGINS.len() as u32);
+
+        for (index, (id, _)) in PLUGINS.iter().enumerate() {
+            let descriptor = factory.plugin_descriptor(index as u32).unwrap();
+            assert_eq!(descriptor.id(), *id);
+        }
+        assert!(factory.plugin_descriptor(factory.plugin_count()).is_none());
+    }
+
+    #[test]
+    fn finds_each_plugin_by_id() {
+        let factory = GainPluginFactory::new();
+
+        for (id, factor) in PLUGINS {
+            let info = factory.find_plugin(id).unwrap();
+            assert_eq!(info.0.id(), id);
+            assert_eq!(info.3, factor);
+        }
+    }
+
+    #[test]
+    fn au_subtypes_are_unique() {
+        let factory = GainPluginFactory::new();
+
+        for index in 0..factory.plugin_count() {
+            let info = factory.get_auv2_info(index).unwrap();
+            let duplicates = (0..index)
+                .filter_map(|other| factory.get_auv2_info(other))
+                .filter(|other| other.au_subtype() == info.au_subtype());
+            assert_eq!(duplicates.count(), 0);
+        }
+        assert!(factory.get_auv2_info(factory.plugin_count()).is_none());
+    }
+
+    #[test]
+    fn au_subtypes_match_the_descriptors() {
+        let factory = GainPluginFactory::new();
+
+        for (id, au_subtype) in [(PLUGINS[0].0, AU_ID_HALVER), (PLUGINS[1].0, AU_ID_DOUBLER)] {
+            let index = (0..factory.plugin_count())
+                .find(|&index| factory.plugin_descriptor(index).unwrap().id() == id)
+                .unwrap();
+            let info = factory.get_auv2_info(index).unwrap();
+            assert_eq!(info.au_subtype(), au_subtype.as_bytes());
+        }
+    }
+}
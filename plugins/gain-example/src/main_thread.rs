@@ -1,28 +1,374 @@
 //! This module handles all CLAP callbacks that run on the main thread.
 
-use clack_extensions::audio_ports::{AudioPortFlags, AudioPortInfo, AudioPortInfoWriter, AudioPortType, PluginAudioPortsImpl};
+use crate::audio_thread::GainMessage;
+use crate::channel::{channel, Receiver, Sender};
+use crate::chunking::CHUNK_SIZE;
+use crate::db::{linear_to_db, parse_gain};
+use crate::params::{report_gain, DEFAULT_FEEDBACK, DEFAULT_MIX, FEEDBACK_PARAM_ID, GAIN_PARAM_ID, MAX_FEEDBACK, MAX_GAIN, MAX_MIX, MAX_PAN, MIN_FEEDBACK, MIN_GAIN, MIN_MIX, MIN_PAN, MIX_PARAM_ID, PAN_PARAM_ID};
+use crate::presets::FactoryPreset;
+use crate::shared::GainShared;
+use crate::state::GainPluginState;
+use clack_extensions::audio_ports::{AudioPortFlags, AudioPortInfo, AudioPortInfoWriter, AudioPortType, HostAudioPorts, PluginAudioPortsImpl, RescanType};
+use clack_extensions::audio_ports_activation::PluginAudioPortsActivationImpl;
+use clack_extensions::audio_ports_config::{AudioPortConfigWriter, AudioPortsConfiguration, MainPortInfo, PluginAudioPortsConfigImpl};
+use clack_extensions::latency::PluginLatencyImpl;
+use clack_extensions::log::LogSeverity;
+use clack_extensions::param_indication::{Color, ParamIndicationAutomation, PluginParamIndicationImpl};
+use clack_extensions::params::{HostParams, ParamDisplayWriter, ParamInfo, ParamInfoFlags, ParamInfoWriter, ParamRescanFlags, PluginMainThreadParams};
+use clack_extensions::preset_discovery::Location;
+use clack_extensions::preset_load::PluginPresetLoadImpl;
+use clack_extensions::remote_controls::{PluginRemoteControlsImpl, RemoteControlsPage, RemoteControlsPageWriter};
+use clack_extensions::render::{PluginRenderImpl, RenderMode};
+use clack_extensions::state::PluginStateImpl;
+use clack_extensions::state_context::{PluginStateContextImpl, StateContextType};
+use clack_extensions::track_info::{HostTrackInfo, PluginTrackInfoImpl, TrackInfoFlags};
+use clack_extensions::voice_info::{PluginVoiceInfoImpl, VoiceInfo, VoiceInfoFlags};
 use clack_plugin::prelude::*;
+use clack_plugin::stream::{InputStream, OutputStream};
+use std::ffi::CStr;
+
+/// The audio port layouts this plugin can be configured with.
+/// Input and output port always share the same layout.
+///
+/// As a gain plugin treats every channel the same,
+/// any channel count works - add more layouts here as needed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PortConfig {
+    Mono,
+    Stereo,
+    /// 5.1 surround (6 channels).
+    Surround51,
+}
+
+impl PortConfig {
+    /// All configurations offered to the host, in the order they are advertised.
+    const ALL: [PortConfig; 3] = [PortConfig::Mono, PortConfig::Stereo, PortConfig::Surround51];
+
+    fn id(self) -> ClapId {
+        match self {
+            PortConfig::Mono => ClapId::new(0),
+            PortConfig::Stereo => ClapId::new(1),
+            PortConfig::Surround51 => ClapId::new(2),
+        }
+    }
+
+    fn name(self) -> &'static [u8] {
+        match self {
+            PortConfig::Mono => b"Mono",
+            PortConfig::Stereo => b"Stereo",
+            PortConfig::Surround51 => b"5.1 Surround",
+        }
+    }
+
+    pub fn channel_count(self) -> u32 {
+        match self {
+            PortConfig::Mono => 1,
+            PortConfig::Stereo => 2,
+            PortConfig::Surround51 => 6,
+        }
+    }
+
+    /// Finds the configuration matching the given channel count, if any.
+    pub fn from_channel_count(channel_count: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.channel_count() == channel_count)
+    }
+
+    fn port_type(self) -> AudioPortType<'static> {
+        match self {
+            PortConfig::Mono => AudioPortType::MONO,
+            PortConfig::Stereo => AudioPortType::STEREO,
+            PortConfig::Surround51 => AudioPortType::SURROUND,
+        }
+    }
+}
+
+/// What the host told us about how a parameter is controlled,
+/// so that a GUI can e.g. make a knob glow in the color of the hardware control it is mapped to.
+#[derive(Copy, Clone, Debug)]
+#[allow(dead_code)] // there is no GUI in example
+pub struct ParamIndication {
+    /// Whether the parameter is mapped to a hardware control (or similar).
+    pub mapped: bool,
+
+    /// The color of the control the parameter is mapped to, if the host chose one.
+    pub mapping_color: Option<Color>,
+
+    /// Whether the parameter has automation, and whether it is currently playing or recording.
+    pub automation: ParamIndicationAutomation,
+
+    /// The color of the automation lane, if the host chose one.
+    pub automation_color: Option<Color>,
+}
+
+impl ParamIndication {
+    /// Neither mapped nor automated, as every parameter starts out.
+    const NONE: Self = Self {
+        mapped: false,
+        mapping_color: None,
+        automation: ParamIndicationAutomation::None,
+        automation_color: None,
+    };
+}
+
+/// How many messages to the audio processor can be pending at once.
+/// This is enough for all changes a GUI makes while the user drags a knob between two blocks.
+const PROCESSOR_MESSAGE_CAPACITY: usize = 64;
 
 pub struct GainPluginMainThread<'a> {
-    #[allow(dead_code)] // unused in example
     host: HostMainThreadHandle<'a>,
 
-    /// The constant factor to multiply incoming samples with.
-    pub factor: f32,
+    /// The parameter values and host extensions, shared with the audio thread.
+    shared: &'a GainShared,
+
+    /// Sends messages to the audio processor while the plugin is activated.
+    processor_messages: Option<Sender<GainMessage>>,
+
+    /// The port layout currently selected by the host.
+    port_config: PortConfig,
+
+    /// A port layout restored from a saved state while activated,
+    /// which is applied once the host restarts the plugin.
+    pending_port_config: Option<PortConfig>,
+
+    /// Whether the host provides buffers for the input port.
+    /// Without them, the audio thread is handed output-only channels,
+    /// so it doesn't need to check this.
+    #[allow(dead_code)] // unused in example
+    input_port_active: bool,
+
+    /// Whether the host provides buffers for the output port.
+    pub output_port_active: bool,
+
+    /// The name of the track this plugin is inserted on, if the host tells us.
+    #[allow(dead_code)] // unused in example
+    track_name: Option<String>,
+
+    /// The channel count of the track this plugin is inserted on, if the host tells us.
+    track_channel_count: Option<u32>,
+
+    /// The indications of all parameters, in the order of their parameter index.
+    param_indications: [ParamIndication; 4],
 }
 
 impl<'a> GainPluginMainThread<'a> {
     /// Creates an instance of the plugin's main thread.
-    /// This plugin will multiply the incoming signal with gain_factor.
-    pub fn create(host: HostMainThreadHandle<'a>, gain_factor: f32) -> Result<Self, PluginError> {
-        // this example main thread doesn't
-        // do anything or hold any data
-        Ok(Self { host, factor: gain_factor })
+    /// This plugin will multiply the incoming signal with the value of the gain parameter.
+    pub fn create(host: HostMainThreadHandle<'a>, shared: &'a GainShared) -> Result<Self, PluginError> {
+        let mut main_thread = Self {
+            host,
+            shared,
+            processor_messages: None,
+            port_config: PortConfig::Stereo,
+            pending_port_config: None,
+            input_port_active: true,
+            output_port_active: true,
+            track_name: None,
+            track_channel_count: None,
+            param_indications: [ParamIndication::NONE; 4],
+        };
+
+        // pick a port layout matching the track we've been inserted on,
+        // so e.g. a mono track doesn't get a stereo plugin.
+        // the host can still select a different configuration afterwards.
+        main_thread.update_track_info();
+        if let Some(config) = main_thread
+            .track_channel_count
+            .and_then(PortConfig::from_channel_count)
+        {
+            main_thread.port_config = config;
+        }
+
+        Ok(main_thread)
+    }
+
+    /// Saves the plugin's state.
+    /// Instance-specific data, like the port layout, is only included if requested.
+    fn save_state(
+        &self,
+        output: &mut OutputStream,
+        include_instance_data: bool,
+    ) -> Result<(), PluginError> {
+        GainPluginState {
+            factor: self.shared.params.factor(),
+            pan: self.shared.params.pan(),
+            delay_time: self.shared.params.delay_time(),
+            feedback: self.shared.params.feedback(),
+            mix: self.shared.params.mix(),
+            port_config: include_instance_data.then_some(self.port_config),
+        }
+        .write(output)
+    }
+
+    /// Reads and restores the plugin's state.
+    fn load_state(&mut self, input: &mut InputStream) -> Result<(), PluginError> {
+        let state = GainPluginState::read(input)?;
+        self.apply_state(state);
+        Ok(())
+    }
+
+    /// Restores the plugin's state.
+    /// Data that isn't contained in the state (e.g. when loading a preset) is left untouched.
+    fn apply_state(&mut self, state: GainPluginState) {
+        self.shared.params.set_factor(state.factor);
+        self.notify_param_changed(GainMessage::GainChanged(self.shared.params.factor()));
+        self.shared.params.set_pan(state.pan);
+        self.notify_param_changed(GainMessage::PanChanged(self.shared.params.pan()));
+        // the delay time isn't a parameter, so the host doesn't need to be told about it.
+        self.shared.params.set_delay_time(state.delay_time);
+        self.shared.params.set_feedback(state.feedback);
+        self.notify_param_changed(GainMessage::FeedbackChanged(self.shared.params.feedback()));
+        self.shared.params.set_mix(state.mix);
+        self.notify_param_changed(GainMessage::MixChanged(self.shared.params.mix()));
+
+        if let Some(port_config) = state.port_config {
+            self.change_port_config(port_config);
+        }
+    }
+
+    /// Changes the port layout from within the plugin, e.g. when restoring a saved state,
+    /// and tells the host about it.
+    ///
+    /// The layout can't change while activated, as the audio processor has been set up for it.
+    /// In that case, we ask the host to restart the plugin and change it while deactivated.
+    fn change_port_config(&mut self, port_config: PortConfig) {
+        if self.processor_messages.is_some() {
+            self.pending_port_config = Some(port_config);
+            self.host.shared().request_restart();
+            return;
+        }
+
+        if port_config == self.port_config {
+            return;
+        }
+        self.port_config = port_config;
+
+        if let Some(audio_ports) = self.host.shared().get_extension::<HostAudioPorts>() {
+            audio_ports.rescan(&mut self.host, RescanType::LIST);
+        }
+    }
+
+    /// The port layout currently selected by the host.
+    pub fn port_config(&self) -> PortConfig {
+        self.port_config
+    }
+
+    /// Creates the channel to a newly activated audio processor,
+    /// returning the half the processor receives messages on.
+    pub fn connect_processor(&mut self) -> Receiver<GainMessage> {
+        let (sender, receiver) = channel(PROCESSOR_MESSAGE_CAPACITY);
+        self.processor_messages = Some(sender);
+        receiver
+    }
+
+    /// Drops the channel to the audio processor when it is deactivated,
+    /// and applies a port layout that was restored while activated.
+    pub fn disconnect_processor(&mut self) {
+        self.processor_messages = None;
+
+        if let Some(port_config) = self.pending_port_config.take() {
+            self.change_port_config(port_config);
+        }
+    }
+
+    /// Called when the user starts changing the gain, e.g. by grabbing a knob in a GUI.
+    /// Every call must be followed by a call to `end_gain_gesture` once the user lets go.
+    #[allow(dead_code)] // there is no GUI in example
+    pub fn begin_gain_gesture(&mut self) {
+        self.send_gesture(GainMessage::GestureBegin);
+    }
+
+    /// Called when the user changes the gain during a gesture, e.g. by dragging a knob in a GUI.
+    #[allow(dead_code)] // there is no GUI in example
+    pub fn set_gain_from_ui(&mut self, factor: f32) {
+        self.shared.params.set_factor(factor);
+        self.notify_param_changed(GainMessage::GainChanged(self.shared.params.factor()));
+    }
+
+    /// Called when the user stops changing the gain, e.g. by releasing a knob in a GUI.
+    #[allow(dead_code)] // there is no GUI in example
+    pub fn end_gain_gesture(&mut self) {
+        self.send_gesture(GainMessage::GestureEnd);
+    }
+
+    /// Asks the audio processor to tell the host about the start or end of a gesture.
+    ///
+    /// Gestures only matter to the host while it is recording automation during playback,
+    /// so they are dropped while deactivated.
+    /// If the queue is full, the host hasn't processed in a long time,
+    /// so it isn't playing back either.
+    fn send_gesture(&mut self, message: GainMessage) {
+        let Some(sender) = &mut self.processor_messages else {
+            return;
+        };
+
+        if sender.send(message).is_ok() {
+            if let Some(host_params) = self.host.shared().get_extension::<HostParams>() {
+                host_params.request_flush(&self.host.shared());
+            }
+        }
+    }
+
+    /// Tells the host that a parameter changed from within the plugin,
+    /// given the message the audio processor reports the change with.
+    fn notify_param_changed(&mut self, message: GainMessage) {
+        let Some(host_params) = self.host.shared().get_extension::<HostParams>() else {
+            return;
+        };
+
+        // while activated, the audio processor reports the new value
+        // in its next process or flush call, which we ask the host for.
+        if let Some(sender) = &mut self.processor_messages {
+            if sender.send(message).is_ok() {
+                host_params.request_flush(&self.host.shared());
+                return;
+            }
+        }
+
+        // otherwise, the host has to re-read all parameter values.
+        host_params.rescan(&mut self.host, ParamRescanFlags::VALUES);
+    }
+
+    /// The indication of the parameter with the given id, if it exists.
+    fn param_indication_mut(&mut self, param_id: ClapId) -> Option<&mut ParamIndication> {
+        if param_id == GAIN_PARAM_ID {
+            Some(&mut self.param_indications[0])
+        } else if param_id == PAN_PARAM_ID {
+            Some(&mut self.param_indications[1])
+        } else if param_id == FEEDBACK_PARAM_ID {
+            Some(&mut self.param_indications[2])
+        } else if param_id == MIX_PARAM_ID {
+            Some(&mut self.param_indications[3])
+        } else {
+            None
+        }
+    }
+
+    /// Re-reads the name and channel count of the track this plugin is inserted on.
+    fn update_track_info(&mut self) {
+        let Some(track_info) = self.host.shared().get_extension::<HostTrackInfo>() else {
+            return;
+        };
+        let Some(info) = track_info.get(&mut self.host) else {
+            return;
+        };
+
+        self.track_name = info
+            .flags
+            .contains(TrackInfoFlags::HAS_TRACK_NAME)
+            .then(|| String::from_utf8_lossy(info.name).into_owned());
+
+        self.track_channel_count = info
+            .flags
+            .contains(TrackInfoFlags::HAS_AUDIO_CHANNEL)
+            .then_some(info.audio_channel_count)
+            .and_then(|count| u32::try_from(count).ok());
     }
 }
 
-impl<'a> PluginMainThread<'a, ()> for GainPluginMainThread<'a> {
+impl<'a> PluginMainThread<'a, GainShared> for GainPluginMainThread<'a> {
     fn on_main_thread(&mut self) {
+        self.shared.thread_checker.debug_assert_main_thread(&self.host.shared());
+
         // in a real plugin, you might exchange information
         // with your GUI or audio thread in this callback.
     }
@@ -43,15 +389,410 @@ impl<'a> PluginAudioPortsImpl for GainPluginMainThread<'a> {
             return;
         }
 
-        // input and output ports are both stereo (2 channels)
-        // and 32-bit only.
+        // input and output ports share the selected layout
+        // and are 32-bit only.
         writer.set(&AudioPortInfo {
             id: ClapId::new(if is_input { 0 } else { 1 }),
             name: b"Audio port",
-            channel_count: 2,
+            channel_count: self.port_config.channel_count(),
             flags: AudioPortFlags::IS_MAIN,
-            port_type: Some(AudioPortType::STEREO),
+            port_type: Some(self.port_config.port_type()),
             in_place_pair: None,
         });
     }
-}
\ No newline at end of file
+}
+
+/// Lets the host choose between the port layouts in [`PortConfig::ALL`].
+/// The host only selects a configuration while the plugin is deactivated,
+/// so the audio thread simply processes whatever channels it is handed.
+impl<'a> PluginAudioPortsConfigImpl for GainPluginMainThread<'a> {
+    fn count(&mut self) -> u32 {
+        PortConfig::ALL.len() as u32
+    }
+
+    fn get(&mut self, index: u32, writer: &mut AudioPortConfigWriter) {
+        let Some(config) = PortConfig::ALL.get(index as usize) else {
+            return;
+        };
+
+        writer.write(&AudioPortsConfiguration {
+            id: config.id(),
+            name: config.name(),
+            input_port_count: 1,
+            output_port_count: 1,
+            main_input: Some(MainPortInfo {
+                channel_count: config.channel_count(),
+                port_type: Some(config.port_type()),
+            }),
+            main_output: Some(MainPortInfo {
+                channel_count: config.channel_count(),
+                port_type: Some(config.port_type()),
+            }),
+        });
+    }
+
+    fn select(&mut self, config_id: ClapId) -> Result<(), PluginError> {
+        let Some(config) = PortConfig::ALL.into_iter().find(|c| c.id() == config_id) else {
+            self.shared.logger.log(
+                &self.host.shared(),
+                LogSeverity::HostMisbehaving,
+                c"Host selected an unknown audio port configuration",
+            );
+            return Err(PluginError::Message("Unknown audio port configuration"));
+        };
+
+        self.port_config = config;
+        Ok(())
+    }
+}
+
+/// Lets the host deactivate ports it has nothing connected to, e.g. to save routing.
+/// Ports are active by default, and the host doesn't provide buffers for inactive ports.
+///
+/// This is most useful for optional ports like a sidechain input,
+/// but the same applies to the main ports:
+/// without an input, this plugin processes silence (so a delay can still ring out),
+/// and without an output, there's nothing to process at all.
+impl<'a> PluginAudioPortsActivationImpl for GainPluginMainThread<'a> {
+    /// Ports are only (de)activated while the plugin is deactivated,
+    /// so the audio thread doesn't have to handle changes while processing.
+    fn can_activate_while_processing(&mut self) -> bool {
+        false
+    }
+
+    fn set_active(
+        &mut self,
+        is_input: bool,
+        port_index: u32,
+        is_active: bool,
+        _sample_size: u32,
+    ) -> bool {
+        if port_index != 0 {
+            return false;
+        }
+
+        match is_input {
+            true => self.input_port_active = is_active,
+            false => self.output_port_active = is_active,
+        }
+        true
+    }
+}
+
+/// Tells the host how many frames the output lags behind the input,
+/// so that it can delay other tracks to keep them in sync.
+/// Only processing in chunks (the "chunked" feature) adds latency.
+/// The latency may only change while deactivated, and then has to be announced to the host.
+impl<'a> PluginLatencyImpl for GainPluginMainThread<'a> {
+    fn get(&mut self) -> u32 {
+        match cfg!(feature = "chunked") {
+            true => CHUNK_SIZE as u32,
+            false => 0,
+        }
+    }
+}
+
+/// The host tells us whether it renders in realtime or offline.
+/// A gain plugin doesn't care, but e.g. a plugin with an expensive
+/// high-quality mode could enable it for offline bounces only.
+impl<'a> PluginRenderImpl for GainPluginMainThread<'a> {
+    fn has_hard_realtime_requirement(&self) -> bool {
+        false
+    }
+
+    fn set(&mut self, mode: RenderMode) -> Result<(), PluginError> {
+        // the host may switch modes while activated,
+        // so the audio processor picks this up on its next process call.
+        self.shared.render_mode.set(mode);
+        Ok(())
+    }
+}
+
+/// Tells the host how many voices the plugin has, which e.g. drives MPE behavior.
+/// An effect like this gain plugin is monophonic, so it reports a single voice.
+/// A synth would report its current and maximum polyphony here.
+impl<'a> PluginVoiceInfoImpl for GainPluginMainThread<'a> {
+    fn get(&self) -> Option<VoiceInfo> {
+        Some(VoiceInfo {
+            voice_count: 1,
+            voice_capacity: 1,
+            flags: VoiceInfoFlags::empty(),
+        })
+    }
+}
+
+/// The host notifies us when the track we're inserted on changes,
+/// e.g. because the user renamed it or changed its channel layout.
+impl<'a> PluginTrackInfoImpl for GainPluginMainThread<'a> {
+    fn changed(&mut self) {
+        self.update_track_info();
+
+        // the port layout is left alone here:
+        // changing it would require the plugin to be deactivated
+        // and the host to rescan our audio ports.
+    }
+}
+
+/// Describes the gain and pan parameters to the host,
+/// and the delay's feedback and mix if the "delay" feature is enabled.
+impl<'a> PluginMainThreadParams for GainPluginMainThread<'a> {
+    fn count(&mut self) -> u32 {
+        // the delay parameters only exist if there is a delay to control.
+        if cfg!(feature = "delay") {
+            4
+        } else {
+            2
+        }
+    }
+
+    fn get_info(&mut self, param_index: u32, info: &mut ParamInfoWriter) {
+        match param_index {
+            0 => info.set(&ParamInfo {
+                id: GAIN_PARAM_ID,
+                flags: ParamInfoFlags::IS_AUTOMATABLE | ParamInfoFlags::IS_MODULATABLE,
+                cookie: Default::default(),
+                name: b"Gain",
+                module: b"",
+                min_value: MIN_GAIN as f64,
+                max_value: MAX_GAIN as f64,
+                default_value: self.shared.params.default_factor() as f64,
+            }),
+            1 => info.set(&ParamInfo {
+                id: PAN_PARAM_ID,
+                flags: ParamInfoFlags::IS_AUTOMATABLE,
+                cookie: Default::default(),
+                name: b"Pan",
+                module: b"",
+                min_value: MIN_PAN as f64,
+                max_value: MAX_PAN as f64,
+                default_value: 0.0,
+            }),
+            2 if cfg!(feature = "delay") => info.set(&ParamInfo {
+                id: FEEDBACK_PARAM_ID,
+                flags: ParamInfoFlags::IS_AUTOMATABLE,
+                cookie: Default::default(),
+                name: b"Feedback",
+                module: b"Delay",
+                min_value: MIN_FEEDBACK as f64,
+                max_value: MAX_FEEDBACK as f64,
+                default_value: DEFAULT_FEEDBACK as f64,
+            }),
+            3 if cfg!(feature = "delay") => info.set(&ParamInfo {
+                id: MIX_PARAM_ID,
+                flags: ParamInfoFlags::IS_AUTOMATABLE,
+                cookie: Default::default(),
+                name: b"Mix",
+                module: b"Delay",
+                min_value: MIN_MIX as f64,
+                max_value: MAX_MIX as f64,
+                default_value: DEFAULT_MIX as f64,
+            }),
+            _ => {}
+        }
+    }
+
+    fn get_value(&mut self, param_id: ClapId) -> Option<f64> {
+        if param_id == GAIN_PARAM_ID {
+            Some(self.shared.params.factor() as f64)
+        } else if param_id == PAN_PARAM_ID {
+            Some(self.shared.params.pan() as f64)
+        } else if cfg!(feature = "delay") && param_id == FEEDBACK_PARAM_ID {
+            Some(self.shared.params.feedback() as f64)
+        } else if cfg!(feature = "delay") && param_id == MIX_PARAM_ID {
+            Some(self.shared.params.mix() as f64)
+        } else {
+            None
+        }
+    }
+
+    fn value_to_text(
+        &mut self,
+        param_id: ClapId,
+        value: f64,
+        writer: &mut ParamDisplayWriter,
+    ) -> std::fmt::Result {
+        use std::fmt::Write;
+
+        if param_id == GAIN_PARAM_ID {
+            // the factor is shown along with its level, e.g. "0.50x (-6.0 dB)".
+            write!(writer, "{value:.2}x ({:.1} dB)", linear_to_db(value as f32))
+        } else if param_id == PAN_PARAM_ID {
+            // the pan is displayed as a percentage towards either side, e.g. "L50", "C" or "R30".
+            match (value * 100.0).round() as i32 {
+                0 => write!(writer, "C"),
+                percent if percent < 0 => write!(writer, "L{}", -percent),
+                percent => write!(writer, "R{percent}"),
+            }
+        } else if cfg!(feature = "delay")
+            && (param_id == FEEDBACK_PARAM_ID || param_id == MIX_PARAM_ID)
+        {
+            write!(writer, "{:.0} %", value * 100.0)
+        } else {
+            Err(std::fmt::Error)
+        }
+    }
+
+    fn text_to_value(&mut self, param_id: ClapId, text: &CStr) -> Option<f64> {
+        let text = text.to_str().ok()?.trim();
+
+        if param_id == GAIN_PARAM_ID {
+            parse_gain(text).map(f64::from)
+        } else if param_id == PAN_PARAM_ID {
+            // accept what we display, as well as plain values like "-0.5".
+            let percent = |text: &str| text.trim().parse::<f64>().ok().map(|p| p / 100.0);
+
+            if text.eq_ignore_ascii_case("c") {
+                Some(0.0)
+            } else if let Some(left) = text.strip_prefix(['L', 'l']) {
+                percent(left).map(|pan| -pan)
+            } else if let Some(right) = text.strip_prefix(['R', 'r']) {
+                percent(right)
+            } else {
+                text.parse().ok()
+            }
+        } else if cfg!(feature = "delay")
+            && (param_id == FEEDBACK_PARAM_ID || param_id == MIX_PARAM_ID)
+        {
+            // accept what we display, with or without the percent sign.
+            let percent = text.strip_suffix('%').unwrap_or(text).trim();
+            percent.parse::<f64>().ok().map(|percent| percent / 100.0)
+        } else {
+            None
+        }
+    }
+
+    /// Called by the host to deliver parameter changes while the plugin is deactivated.
+    /// While activated, the audio processor's `flush` is called instead.
+    fn flush(
+        &mut self,
+        input_parameter_changes: &InputEvents,
+        output_parameter_changes: &mut OutputEvents,
+    ) {
+        if let Some(factor) = self.shared.params.handle_events(input_parameter_changes) {
+            // there's no next block to retry in while deactivated,
+            // but the host can still read the corrected value through get_value.
+            let _ = report_gain(output_parameter_changes, factor);
+        }
+    }
+}
+
+/// Tells hardware controllers (e.g. Push) which parameters to put on their knobs.
+/// Controllers show parameters in pages of up to 8 knobs,
+/// and this plugin has few enough parameters to fit on a single page.
+impl<'a> PluginRemoteControlsImpl for GainPluginMainThread<'a> {
+    fn count(&mut self) -> u32 {
+        1
+    }
+
+    fn get(&mut self, page_index: u32, page: &mut RemoteControlsPageWriter) {
+        if page_index != 0 {
+            return;
+        }
+
+        // the gain is the plugin's main control, so it lands on the first knob.
+        // unused knobs are left empty.
+        let mut param_ids = [None; 8];
+        param_ids[0] = Some(GAIN_PARAM_ID);
+        param_ids[1] = Some(PAN_PARAM_ID);
+        if cfg!(feature = "delay") {
+            param_ids[2] = Some(FEEDBACK_PARAM_ID);
+            param_ids[3] = Some(MIX_PARAM_ID);
+        }
+
+        page.set(&RemoteControlsPage {
+            section_name: b"Main",
+            page_id: 0,
+            page_name: b"Main",
+            param_ids,
+            is_for_preset: false,
+        });
+    }
+}
+
+/// The host tells us when a parameter gets mapped to a hardware control or automated,
+/// so that the GUI can indicate it, e.g. by coloring the respective knob.
+/// The indications are only stored, as this example has no GUI to show them in.
+impl<'a> PluginParamIndicationImpl for GainPluginMainThread<'a> {
+    fn set_mapping(
+        &mut self,
+        param_id: ClapId,
+        has_mapping: bool,
+        color: Option<Color>,
+        _label: Option<&CStr>,
+        _description: Option<&CStr>,
+    ) {
+        if let Some(indication) = self.param_indication_mut(param_id) {
+            indication.mapped = has_mapping;
+            indication.mapping_color = color;
+        }
+    }
+
+    fn set_automation(
+        &mut self,
+        param_id: ClapId,
+        automation_state: ParamIndicationAutomation,
+        color: Option<Color>,
+    ) {
+        if let Some(indication) = self.param_indication_mut(param_id) {
+            indication.automation = automation_state;
+            indication.automation_color = color;
+        }
+    }
+}
+
+/// Saves and restores the plugin's state, e.g. when the host saves or opens a project.
+/// See the state module for the format and how it is versioned.
+impl<'a> PluginStateImpl for GainPluginMainThread<'a> {
+    fn save(&mut self, output: &mut OutputStream) -> Result<(), PluginError> {
+        self.save_state(output, true)
+    }
+
+    fn load(&mut self, input: &mut InputStream) -> Result<(), PluginError> {
+        self.load_state(input)
+    }
+}
+
+/// Hosts supporting the state-context extension tell us why the state is being saved.
+/// Presets should sound the same on any track,
+/// so they omit instance-specific data like the port layout.
+impl<'a> PluginStateContextImpl for GainPluginMainThread<'a> {
+    fn save(
+        &mut self,
+        output: &mut OutputStream,
+        context: StateContextType,
+    ) -> Result<(), PluginError> {
+        let include_instance_data = !matches!(context, StateContextType::ForPreset);
+        self.save_state(output, include_instance_data)
+    }
+
+    fn load(
+        &mut self,
+        input: &mut InputStream,
+        _context: StateContextType,
+    ) -> Result<(), PluginError> {
+        // the state itself records which data it contains.
+        self.load_state(input)
+    }
+}
+
+/// Loads a preset the user picked in the host's preset browser.
+/// Only the factory presets advertised by the preset-discovery factory are supported.
+impl<'a> PluginPresetLoadImpl for GainPluginMainThread<'a> {
+    fn load_from_location(
+        &mut self,
+        location: Location,
+        load_key: Option<&CStr>,
+    ) -> Result<(), PluginError> {
+        // factory presets are built into the plugin, so they don't have a file location.
+        let (Location::Plugin, Some(load_key)) = (location, load_key) else {
+            return Err(PluginError::Message("Only factory presets can be loaded"));
+        };
+
+        let Some(preset) = FactoryPreset::find(load_key) else {
+            return Err(PluginError::Message("Unknown factory preset"));
+        };
+
+        self.apply_state(preset.state());
+        Ok(())
+    }
+}
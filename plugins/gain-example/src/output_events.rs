@@ -0,0 +1,19 @@
+//! This module helps with sending events to the host from the audio thread.
+
+use clack_plugin::prelude::*;
+
+/// Tries to push an event into the host's output event queue.
+///
+/// The queue is owned by the host, which decides how many events it can hold.
+/// Allocating or waiting for space is not an option on the audio thread,
+/// so if the queue is full, the event is handed back to the caller
+/// instead of being dropped. The caller can then e.g. retry in the next process call.
+pub fn try_push_event<E: AsRef<UnknownEvent>>(
+    output: &mut OutputEvents,
+    event: E,
+) -> Result<(), E> {
+    match output.try_push(event.as_ref()) {
+        Ok(()) => Ok(()),
+        Err(_) => Err(event),
+    }
+}
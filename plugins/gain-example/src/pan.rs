@@ -0,0 +1,17 @@
+//! This module implements the pan law used by the pan parameter.
+//!
+//! Unlike the gain, panning treats the left and right channel differently:
+//! moving the signal towards one side makes it louder there and quieter on the other side.
+
+use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+/// The gains of the left and right channel for the given pan position,
+/// which ranges from -1 (hard left) over 0 (center) to 1 (hard right).
+///
+/// This is a constant-power pan law, so a signal keeps its perceived loudness while moving.
+/// It is scaled so that the center leaves the signal untouched,
+/// which means a hard-panned channel is boosted by 3 dB.
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+    let angle = (pan + 1.0) * FRAC_PI_4;
+    (angle.cos() * SQRT_2, angle.sin() * SQRT_2)
+}
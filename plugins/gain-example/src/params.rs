@@ -0,0 +1,274 @@
+//! This module defines the plugin's parameters
+//! and how incoming parameter events are applied to them.
+//!
+//! The parameter values are shared between the main thread and the audio thread,
+//! so that changes made by the host on either thread end up in the saved state.
+
+use crate::output_events::try_push_event;
+use crate::shared::RtState;
+use clack_plugin::events::event_types::{
+    ParamGestureBeginEvent, ParamGestureEndEvent, ParamValueEvent,
+};
+use clack_plugin::events::spaces::CoreEventSpace;
+use clack_plugin::events::Pckn;
+use clack_plugin::prelude::*;
+use clack_plugin::utils::Cookie;
+
+/// The id of the gain parameter, which is the factor to multiply incoming samples with.
+pub const GAIN_PARAM_ID: ClapId = ClapId::new(0);
+
+pub const MIN_GAIN: f32 = 0.0;
+pub const MAX_GAIN: f32 = 4.0;
+
+/// The id of the pan parameter, which moves a stereo signal between the left and right channel.
+pub const PAN_PARAM_ID: ClapId = ClapId::new(1);
+
+pub const MIN_PAN: f32 = -1.0;
+pub const MAX_PAN: f32 = 1.0;
+
+/// The id of the feedback parameter, which is how much of each echo is fed back into the delay.
+/// Like the mix, it is only exposed if the "delay" feature is enabled.
+pub const FEEDBACK_PARAM_ID: ClapId = ClapId::new(2);
+
+pub const MIN_FEEDBACK: f32 = 0.0;
+/// Below 1, so that the echoes always die down.
+pub const MAX_FEEDBACK: f32 = 0.95;
+pub const DEFAULT_FEEDBACK: f32 = 0.5;
+
+/// The id of the mix parameter, which balances the dry signal against the echoes of the delay.
+pub const MIX_PARAM_ID: ClapId = ClapId::new(3);
+
+pub const MIN_MIX: f32 = 0.0;
+pub const MAX_MIX: f32 = 1.0;
+pub const DEFAULT_MIX: f32 = 0.3;
+
+/// The time between the input and its first echo, in seconds.
+/// This is part of the state, but not a parameter:
+/// changing it while echoes are ringing out would make them jump.
+pub const MIN_DELAY_TIME: f32 = 0.01;
+pub const MAX_DELAY_TIME: f32 = 2.0;
+pub const DEFAULT_DELAY_TIME: f32 = 0.3;
+
+/// The parameter values of a plugin instance.
+pub struct GainParams {
+    /// The current gain factor.
+    /// The audio thread reads it every block, while either thread may change it.
+    factor: RtState<f32>,
+
+    /// The modulation amount the host currently applies on top of the factor.
+    /// Unlike the factor itself, this is not part of the plugin's state.
+    modulation: RtState<f32>,
+
+    /// The gain factor the plugin variant was created with.
+    default_factor: f32,
+
+    /// The current pan position, from -1 (hard left) over 0 (center) to 1 (hard right).
+    pan: RtState<f32>,
+
+    /// How much of each echo is fed back into the delay, producing the next echo.
+    feedback: RtState<f32>,
+
+    /// How much of the echoes is mixed into the output, from 0 (dry only) to 1 (echoes only).
+    mix: RtState<f32>,
+
+    /// The time between the input and its first echo, in seconds.
+    delay_time: RtState<f32>,
+}
+
+impl GainParams {
+    pub fn new(default_factor: f32) -> Self {
+        Self {
+            factor: RtState::new(default_factor),
+            modulation: RtState::new(0.0),
+            default_factor,
+            pan: RtState::new(0.0),
+            feedback: RtState::new(DEFAULT_FEEDBACK),
+            mix: RtState::new(DEFAULT_MIX),
+            delay_time: RtState::new(DEFAULT_DELAY_TIME),
+        }
+    }
+
+    /// The factor to multiply incoming samples with.
+    pub fn factor(&self) -> f32 {
+        self.factor.get()
+    }
+
+    /// The factor to multiply incoming samples with, including the host's modulation.
+    pub fn modulated_factor(&self) -> f32 {
+        (self.factor() + self.modulation.get()).clamp(MIN_GAIN, MAX_GAIN)
+    }
+
+    /// Sets the gain factor, clamped to the parameter's range.
+    /// Returns the value actually set if it differs from the given one.
+    pub fn set_factor(&self, factor: f32) -> Option<f32> {
+        let clamped = match factor.is_nan() {
+            true => self.default_factor,
+            false => factor.clamp(MIN_GAIN, MAX_GAIN),
+        };
+        self.factor.set(clamped);
+
+        (clamped != factor).then_some(clamped)
+    }
+
+    pub fn default_factor(&self) -> f32 {
+        self.default_factor
+    }
+
+    /// The position to pan a stereo signal to.
+    pub fn pan(&self) -> f32 {
+        self.pan.get()
+    }
+
+    /// Sets the pan position, clamped to the parameter's range.
+    pub fn set_pan(&self, pan: f32) {
+        let clamped = match pan.is_nan() {
+            true => 0.0,
+            false => pan.clamp(MIN_PAN, MAX_PAN),
+        };
+        self.pan.set(clamped);
+    }
+
+    /// How much of each echo is fed back into the delay.
+    pub fn feedback(&self) -> f32 {
+        self.feedback.get()
+    }
+
+    /// Sets the delay's feedback, clamped to the parameter's range.
+    pub fn set_feedback(&self, feedback: f32) {
+        let clamped = match feedback.is_nan() {
+            true => DEFAULT_FEEDBACK,
+            false => feedback.clamp(MIN_FEEDBACK, MAX_FEEDBACK),
+        };
+        self.feedback.set(clamped);
+    }
+
+    /// How much of the delay's echoes is mixed into the output.
+    pub fn mix(&self) -> f32 {
+        self.mix.get()
+    }
+
+    /// Sets the delay's wet/dry mix, clamped to the parameter's range.
+    pub fn set_mix(&self, mix: f32) {
+        let clamped = match mix.is_nan() {
+            true => DEFAULT_MIX,
+            false => mix.clamp(MIN_MIX, MAX_MIX),
+        };
+        self.mix.set(clamped);
+    }
+
+    /// The time between the input and its first echo, in seconds.
+    pub fn delay_time(&self) -> f32 {
+        self.delay_time.get()
+    }
+
+    /// Sets the delay time, clamped to the supported range.
+    pub fn set_delay_time(&self, delay_time: f32) {
+        let clamped = match delay_time.is_nan() {
+            true => DEFAULT_DELAY_TIME,
+            false => delay_time.clamp(MIN_DELAY_TIME, MAX_DELAY_TIME),
+        };
+        self.delay_time.set(clamped);
+    }
+
+    /// Applies all parameter changes in the given event list.
+    ///
+    /// The host delivers parameter changes as part of `process`,
+    /// or through the params extension's `flush` while the plugin isn't processing
+    /// (e.g. because the transport is stopped), so both call this.
+    ///
+    /// If the host sent a gain value outside of the parameter's range,
+    /// the corrected value is returned so that it can be reported back.
+    pub fn handle_events(&self, events: &InputEvents) -> Option<f32> {
+        let mut corrected = None;
+
+        for event in events {
+            match event.as_core_event() {
+                // a value event changes the parameter itself, e.g. due to automation.
+                Some(CoreEventSpace::ParamValue(event))
+                    if event.param_id() == Some(GAIN_PARAM_ID) =>
+                {
+                    corrected = self.set_factor(event.value() as f32);
+                }
+                // the pan can't be modulated, and out-of-range values are simply clamped.
+                Some(CoreEventSpace::ParamValue(event))
+                    if event.param_id() == Some(PAN_PARAM_ID) =>
+                {
+                    self.set_pan(event.value() as f32);
+                }
+                // neither can the delay parameters.
+                Some(CoreEventSpace::ParamValue(event))
+                    if event.param_id() == Some(FEEDBACK_PARAM_ID) =>
+                {
+                    self.set_feedback(event.value() as f32);
+                }
+                Some(CoreEventSpace::ParamValue(event))
+                    if event.param_id() == Some(MIX_PARAM_ID) =>
+                {
+                    self.set_mix(event.value() as f32);
+                }
+                // a modulation event offsets the parameter temporarily, e.g. by an LFO in the host,
+                // and replaces the previous modulation amount.
+                // this plugin is monophonic, so per-voice modulation is treated as global.
+                Some(CoreEventSpace::ParamMod(event))
+                    if event.param_id() == Some(GAIN_PARAM_ID) =>
+                {
+                    let amount = event.amount() as f32;
+                    if amount.is_finite() {
+                        self.modulation.set(amount);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        corrected
+    }
+}
+
+/// Tells the host that the plugin changed the gain parameter on its own.
+/// If the host's output queue is full, the value is handed back to retry later.
+pub fn report_gain(output: &mut OutputEvents, factor: f32) -> Result<(), f32> {
+    report_value(output, GAIN_PARAM_ID, factor)
+}
+
+/// Tells the host that the plugin changed the pan parameter on its own.
+/// If the host's output queue is full, the value is handed back to retry later.
+pub fn report_pan(output: &mut OutputEvents, pan: f32) -> Result<(), f32> {
+    report_value(output, PAN_PARAM_ID, pan)
+}
+
+/// Tells the host that the plugin changed the delay's feedback parameter on its own.
+/// If the host's output queue is full, the value is handed back to retry later.
+pub fn report_feedback(output: &mut OutputEvents, feedback: f32) -> Result<(), f32> {
+    report_value(output, FEEDBACK_PARAM_ID, feedback)
+}
+
+/// Tells the host that the plugin changed the delay's mix parameter on its own.
+/// If the host's output queue is full, the value is handed back to retry later.
+pub fn report_mix(output: &mut OutputEvents, mix: f32) -> Result<(), f32> {
+    report_value(output, MIX_PARAM_ID, mix)
+}
+
+fn report_value(output: &mut OutputEvents, param_id: ClapId, value: f32) -> Result<(), f32> {
+    let event = ParamValueEvent::new(
+        0,
+        param_id,
+        Pckn::match_all(),
+        value as f64,
+        Cookie::empty(),
+    );
+
+    try_push_event(output, event).map_err(|_| value)
+}
+
+/// Tells the host that the user started changing the gain parameter, e.g. by grabbing a knob.
+/// Hosts rely on this to record automation while the user adjusts the parameter,
+/// and to group the changes into a single undo step.
+pub fn report_gesture_begin(output: &mut OutputEvents) -> Result<(), ParamGestureBeginEvent> {
+    try_push_event(output, ParamGestureBeginEvent::new(0, GAIN_PARAM_ID))
+}
+
+/// Tells the host that the user stopped changing the gain parameter, e.g. by releasing a knob.
+pub fn report_gesture_end(output: &mut OutputEvents) -> Result<(), ParamGestureEndEvent> {
+    try_push_event(output, ParamGestureEndEvent::new(0, GAIN_PARAM_ID))
+}
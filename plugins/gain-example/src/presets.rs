@@ -0,0 +1,155 @@
+//! This module exposes the presets that ship with the plugin
+//! to the host's preset browser, using CLAP's preset-discovery factory.
+//!
+//! When the user picks one of these presets, the host asks the plugin instance
+//! to load it through the preset-load extension (see the main thread).
+
+use crate::params::{DEFAULT_DELAY_TIME, DEFAULT_FEEDBACK, DEFAULT_MIX};
+use crate::state::GainPluginState;
+use clack_extensions::preset_discovery::prelude::*;
+use std::ffi::{CStr, CString};
+
+/// A preset that is built into the plugin binary.
+pub struct FactoryPreset {
+    /// The name shown in the host's preset browser.
+    pub name: &'static CStr,
+
+    /// Identifies the preset when the host asks us to load it.
+    pub load_key: &'static CStr,
+
+    /// The factor to multiply incoming samples with.
+    pub factor: f32,
+}
+
+impl FactoryPreset {
+    /// Finds the factory preset with the given load key.
+    pub fn find(load_key: &CStr) -> Option<&'static Self> {
+        FACTORY_PRESETS
+            .iter()
+            .find(|preset| preset.load_key == load_key)
+    }
+
+    /// The plugin state this preset represents.
+    /// Like any preset, it doesn't contain instance-specific data.
+    pub fn state(&self) -> GainPluginState {
+        GainPluginState {
+            factor: self.factor,
+            // all factory presets are centered.
+            pan: 0.0,
+            // the factory presets only differ in their gain.
+            delay_time: DEFAULT_DELAY_TIME,
+            feedback: DEFAULT_FEEDBACK,
+            mix: DEFAULT_MIX,
+            port_config: None,
+        }
+    }
+}
+
+pub static FACTORY_PRESETS: [FactoryPreset; 3] = [
+    FactoryPreset {
+        name: c"Halve",
+        load_key: c"halve",
+        factor: 0.5,
+    },
+    FactoryPreset {
+        name: c"Double",
+        load_key: c"double",
+        factor: 2.0,
+    },
+    FactoryPreset {
+        name: c"Unity",
+        load_key: c"unity",
+        factor: 1.0,
+    },
+];
+
+/// The preset-discovery factory exposes a single provider,
+/// which lists the factory presets for all plugins in this binary.
+pub struct GainPresetDiscoveryFactory {
+    provider: ProviderDescriptor,
+
+    /// The ids of the plugins the factory presets can be loaded into.
+    plugin_ids: Vec<CString>,
+}
+
+impl GainPresetDiscoveryFactory {
+    pub fn new(plugin_ids: Vec<CString>) -> Self {
+        Self {
+            provider: ProviderDescriptor::new(
+                c"free-audio.clap.rust-gain-example.presets",
+                c"Gain Example Factory Presets",
+            )
+            .with_vendor(c"free-audio"),
+            plugin_ids,
+        }
+    }
+}
+
+impl PresetDiscoveryFactoryImpl for GainPresetDiscoveryFactory {
+    fn provider_count(&self) -> u32 {
+        1
+    }
+
+    fn provider_descriptor(&self, index: u32) -> Option<&ProviderDescriptor> {
+        match index {
+            0 => Some(&self.provider),
+            _ => None,
+        }
+    }
+
+    fn create_provider<'a>(
+        &'a self,
+        indexer: Indexer<'a>,
+        provider_id: &CStr,
+    ) -> Option<ProviderInstance<'a>> {
+        if provider_id != self.provider.id() {
+            return None;
+        }
+
+        Some(ProviderInstance::new(indexer, &self.provider, |indexer| {
+            GainPresetProvider::new(indexer, &self.plugin_ids)
+        }))
+    }
+}
+
+/// Tells the host's indexer about the factory presets.
+struct GainPresetProvider<'a> {
+    plugin_ids: &'a [CString],
+}
+
+impl<'a> GainPresetProvider<'a> {
+    fn new(indexer: &mut Indexer, plugin_ids: &'a [CString]) -> Self {
+        // factory presets don't live in files,
+        // so they're declared at the special "plugin" location.
+        let _ = indexer.declare_location(LocationInfo {
+            name: c"Factory Presets",
+            flags: Flags::IS_FACTORY_CONTENT,
+            location: Location::Plugin,
+        });
+
+        Self { plugin_ids }
+    }
+}
+
+impl<'a> ProviderImpl<'a> for GainPresetProvider<'a> {
+    fn get_metadata(&mut self, location: Location, receiver: &mut MetadataReceiver) {
+        if !matches!(location, Location::Plugin) {
+            return;
+        }
+
+        for preset in &FACTORY_PRESETS {
+            if receiver
+                .begin_preset(Some(preset.name), Some(preset.load_key))
+                .is_err()
+            {
+                return;
+            }
+
+            for plugin_id in self.plugin_ids {
+                receiver.add_plugin_id(&UniversalPluginId::clap(plugin_id));
+            }
+
+            receiver.set_flags(Flags::IS_FACTORY_CONTENT);
+        }
+    }
+}
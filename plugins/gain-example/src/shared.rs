@@ -0,0 +1,111 @@
+//! This module defines the state that is shared between the main thread and the audio thread.
+//!
+//! Sharing state works best for data that is set up once when the plugin instance is created
+//! and only read afterwards, such as the host's extensions or e.g. a loaded wavetable:
+//! it needs no synchronization, and every thread can access it without copying it around.
+//!
+//! Data that one thread changes while the other one reads it needs more care.
+//! A value that is read continuously, like a parameter, can be shared using an [`RtState`],
+//! through which readers always see the latest value.
+//! Discrete events, which each need to be handled, are better sent through a queue
+//! (see the channel module), so that none of them get lost.
+
+use crate::host_log::HostLogger;
+use crate::params::GainParams;
+use crate::thread_check::ThreadChecker;
+use clack_extensions::render::RenderMode;
+use clack_plugin::prelude::*;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub struct GainShared {
+    /// The parameter values, which the host may change on either thread.
+    pub params: GainParams,
+
+    /// Verifies in debug builds that we're called on the expected thread.
+    /// The host's extensions are queried once and never change afterwards.
+    pub thread_checker: ThreadChecker,
+
+    /// Forwards diagnostic messages to the host's log.
+    pub logger: HostLogger,
+
+    /// Whether the host is rendering in realtime or offline (bouncing).
+    /// The host may change this on the main thread while the audio thread is processing.
+    pub render_mode: RtState<RenderMode>,
+}
+
+impl GainShared {
+    /// Called when the plugin instance is created, before its main thread.
+    pub fn new(host: HostSharedHandle, default_factor: f32) -> Self {
+        Self {
+            params: GainParams::new(default_factor),
+            thread_checker: ThreadChecker::new(&host),
+            logger: HostLogger::new(&host),
+            render_mode: RtState::new(RenderMode::Realtime),
+        }
+    }
+}
+
+impl<'a> PluginShared<'a> for GainShared {}
+
+/// A value that any thread can read or overwrite at any time,
+/// without ever locking or waiting for another thread.
+///
+/// This is a single atomic cell, accessed with relaxed ordering:
+/// a write replaces the value in place, and a reader sees either the old or the new value.
+/// Unlike a queue, it only holds the latest value:
+/// a reader doesn't have to drain anything, but also doesn't see intermediate values.
+/// This limits it to small `Copy` types, which implement [`RtValue`].
+pub struct RtState<T> {
+    bits: AtomicU64,
+    value_type: PhantomData<T>,
+}
+
+impl<T: RtValue> RtState<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            bits: AtomicU64::new(value.to_bits()),
+            value_type: PhantomData,
+        }
+    }
+
+    /// The latest value written by any thread.
+    pub fn get(&self) -> T {
+        // each value is independent of any other memory, so no ordering is required.
+        T::from_bits(self.bits.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, value: T) {
+        self.bits.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// A type that can be stored in an [`RtState`], as it fits into 64 bits.
+/// Only the types the plugin shares are implemented, add more as needed.
+pub trait RtValue: Copy {
+    fn to_bits(self) -> u64;
+    fn from_bits(bits: u64) -> Self;
+}
+
+impl RtValue for f32 {
+    fn to_bits(self) -> u64 {
+        f32::to_bits(self) as u64
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        f32::from_bits(bits as u32)
+    }
+}
+
+impl RtValue for RenderMode {
+    fn to_bits(self) -> u64 {
+        matches!(self, RenderMode::Offline) as u64
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        match bits {
+            0 => RenderMode::Realtime,
+            _ => RenderMode::Offline,
+        }
+    }
+}
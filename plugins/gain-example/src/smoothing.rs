@@ -0,0 +1,69 @@
+//! This module smooths parameter changes on the audio thread.
+//!
+//! Applying a new gain abruptly causes an audible click,
+//! so the gain is ramped linearly towards a new value over a short time instead.
+
+/// Ramps linearly from the current value to a target value over a fixed number of frames.
+pub struct LinearSmoother {
+    /// The value at the start of the current block.
+    current: f32,
+    target: f32,
+
+    /// The change per frame while ramping.
+    step: f32,
+
+    /// How many frames are left until the target is reached.
+    remaining_frames: u32,
+
+    /// How many frames a full ramp takes.
+    ramp_frames: u32,
+}
+
+impl LinearSmoother {
+    /// Creates a smoother resting at the given value,
+    /// whose ramps take `ramp_time` seconds at the given sample rate.
+    pub fn new(value: f32, ramp_time: f64, sample_rate: f64) -> Self {
+        Self {
+            current: value,
+            target: value,
+            step: 0.0,
+            remaining_frames: 0,
+            ramp_frames: ((ramp_time * sample_rate) as u32).max(1),
+        }
+    }
+
+    /// Starts ramping towards the given value, starting from the current block's first frame.
+    pub fn set_target(&mut self, target: f32) {
+        if target == self.target {
+            return;
+        }
+
+        self.target = target;
+        self.remaining_frames = self.ramp_frames;
+        self.step = (target - self.current) / self.ramp_frames as f32;
+    }
+
+    /// The value for the whole current block, if it has reached its target.
+    pub fn settled_value(&self) -> Option<f32> {
+        (self.remaining_frames == 0).then_some(self.target)
+    }
+
+    /// The value at the given frame of the current block.
+    pub fn value_at(&self, frame: u32) -> f32 {
+        match frame < self.remaining_frames {
+            true => self.current + self.step * (frame + 1) as f32,
+            false => self.target,
+        }
+    }
+
+    /// Moves on to the next block, after the current one with the given number of frames.
+    pub fn advance(&mut self, frames: u32) {
+        if frames >= self.remaining_frames {
+            self.current = self.target;
+            self.remaining_frames = 0;
+        } else {
+            self.current += self.step * frames as f32;
+            self.remaining_frames -= frames;
+        }
+    }
+}
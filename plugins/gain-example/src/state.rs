@@ -0,0 +1,278 @@
+//! This module defines how the plugin's state is saved to and loaded from the host.
+//!
+//! Every saved blob starts with a small header containing the format version
+//! and the length of the payload that follows. When the format changes,
+//! bump [`STATE_VERSION`] and teach [`migrate`] how to upgrade payloads
+//! saved by older versions, so that existing projects keep opening after an update.
+
+use crate::main_thread::PortConfig;
+use crate::params::{DEFAULT_DELAY_TIME, DEFAULT_FEEDBACK, DEFAULT_MIX};
+use clack_plugin::prelude::*;
+use std::io::{Read, Write};
+
+/// The version of the state format written by this build of the plugin.
+const STATE_VERSION: u16 = 3;
+
+/// The largest payload we accept, which leaves plenty of room for future fields.
+/// The length is read from the saved blob, and a corrupt or malicious project
+/// must not make us allocate gigabytes of memory.
+const MAX_PAYLOAD_LEN: u32 = 1024;
+
+/// The part of the plugin that is persisted by the host.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GainPluginState {
+    /// The factor to multiply incoming samples with.
+    pub factor: f32,
+
+    /// The position to pan a stereo signal to.
+    pub pan: f32,
+
+    /// The time between the input and the delay's first echo, in seconds.
+    pub delay_time: f32,
+
+    /// How much of each echo is fed back into the delay.
+    pub feedback: f32,
+
+    /// How much of the delay's echoes is mixed into the output.
+    pub mix: f32,
+
+    /// The selected audio port layout.
+    /// This is specific to the plugin instance and its routing in the host,
+    /// so it is omitted when saving a preset.
+    pub port_config: Option<PortConfig>,
+}
+
+impl GainPluginState {
+    /// Writes the header and the payload in the current format version.
+    pub fn write(&self, output: &mut impl Write) -> Result<(), PluginError> {
+        let payload = self.to_payload();
+
+        output.write_all(&STATE_VERSION.to_le_bytes())?;
+        output.write_all(&(payload.len() as u32).to_le_bytes())?;
+        output.write_all(&payload)?;
+
+        Ok(())
+    }
+
+    /// Reads a blob saved with any known format version,
+    /// upgrading it to the current version if necessary.
+    pub fn read(input: &mut impl Read) -> Result<Self, PluginError> {
+        let mut version = [0; 2];
+        input.read_exact(&mut version)?;
+
+        let mut payload_len = [0; 4];
+        input.read_exact(&mut payload_len)?;
+
+        let payload_len = u32::from_le_bytes(payload_len);
+        if payload_len > MAX_PAYLOAD_LEN {
+            return Err(PluginError::Message("Plugin state is too large"));
+        }
+
+        let mut payload = vec![0; payload_len as usize];
+        input.read_exact(&mut payload)?;
+
+        let payload = migrate(u16::from_le_bytes(version), payload)?;
+        Self::from_payload(&payload)
+    }
+
+    fn to_payload(self) -> Vec<u8> {
+        // the port layout is stored by its channel count, 0 meaning "not saved".
+        let channel_count = self.port_config.map_or(0, PortConfig::channel_count);
+
+        let mut payload = Vec::with_capacity(24);
+        payload.extend(self.factor.to_le_bytes());
+        payload.extend(channel_count.to_le_bytes());
+        payload.extend(self.pan.to_le_bytes());
+        payload.extend(self.delay_time.to_le_bytes());
+        payload.extend(self.feedback.to_le_bytes());
+        payload.extend(self.mix.to_le_bytes());
+        payload
+    }
+
+    /// Parses a payload in the current format version.
+    fn from_payload(payload: &[u8]) -> Result<Self, PluginError> {
+        if payload.len() < 24 {
+            return Err(PluginError::Message("Plugin state is truncated"));
+        }
+
+        // every field is 4 bytes long, in the order they are written in.
+        let field =
+            |index: usize| -> [u8; 4] { payload[index * 4..index * 4 + 4].try_into().unwrap() };
+
+        Ok(Self {
+            factor: f32::from_le_bytes(field(0)),
+            port_config: PortConfig::from_channel_count(u32::from_le_bytes(field(1))),
+            pan: f32::from_le_bytes(field(2)),
+            delay_time: f32::from_le_bytes(field(3)),
+            feedback: f32::from_le_bytes(field(4)),
+            mix: f32::from_le_bytes(field(5)),
+        })
+    }
+}
+
+/// Upgrades a payload saved with the given format version to the current format version.
+///
+/// When bumping [`STATE_VERSION`], add an arm that converts the previous version's payload
+/// into the next one (e.g. by appending default values for newly added fields),
+/// and keep the existing arms so that every older version is upgraded step by step.
+fn migrate(version: u16, mut payload: Vec<u8>) -> Result<Vec<u8>, PluginError> {
+    match version {
+        // version 0 only contained the gain factor.
+        // the port layout wasn't saved, which version 1 expresses as a channel count of 0.
+        0 => {
+            payload.extend(0u32.to_le_bytes());
+            migrate(1, payload)
+        }
+        // version 1 didn't have the pan parameter, which is centered by default.
+        1 => {
+            payload.extend(0f32.to_le_bytes());
+            migrate(2, payload)
+        }
+        // version 2 didn't save the delay settings, so the defaults are used.
+        2 => {
+            payload.extend(DEFAULT_DELAY_TIME.to_le_bytes());
+            payload.extend(DEFAULT_FEEDBACK.to_le_bytes());
+            payload.extend(DEFAULT_MIX.to_le_bytes());
+            migrate(3, payload)
+        }
+        STATE_VERSION => Ok(payload),
+        _ => Err(PluginError::Message(
+            "Plugin state was saved by a newer version of this plugin",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a blob as an older version of the plugin would have saved it.
+    fn blob(version: u16, payload: &[u8]) -> Vec<u8> {
+        let mut blob = Vec::new();
+        blob.extend(version.to_le_bytes());
+        blob.extend((payload.len() as u32).to_le_bytes());
+        blob.extend(payload);
+        blob
+    }
+
+    fn round_trip(state: GainPluginState) -> GainPluginState {
+        let mut saved = Vec::new();
+        state.write(&mut saved).unwrap();
+        GainPluginState::read(&mut saved.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn round_trip_keeps_all_fields() {
+        let state = GainPluginState {
+            factor: 0.5,
+            pan: -0.25,
+            delay_time: 1.5,
+            feedback: 0.75,
+            mix: 0.5,
+            port_config: Some(PortConfig::Surround51),
+        };
+        assert_eq!(round_trip(state), state);
+    }
+
+    #[test]
+    fn round_trip_without_port_config() {
+        let state = GainPluginState {
+            factor: 2.0,
+            pan: 1.0,
+            delay_time: DEFAULT_DELAY_TIME,
+            feedback: 0.0,
+            mix: 1.0,
+            port_config: None,
+        };
+        assert_eq!(round_trip(state), state);
+    }
+
+    #[test]
+    fn version_0_is_migrated() {
+        let saved = blob(0, &0.75f32.to_le_bytes());
+        let state = GainPluginState::read(&mut saved.as_slice()).unwrap();
+
+        assert_eq!(
+            state,
+            GainPluginState {
+                factor: 0.75,
+                pan: 0.0,
+                delay_time: DEFAULT_DELAY_TIME,
+                feedback: DEFAULT_FEEDBACK,
+                mix: DEFAULT_MIX,
+                port_config: None,
+            }
+        );
+    }
+
+    #[test]
+    fn version_1_is_migrated() {
+        let mut payload = Vec::new();
+        payload.extend(1.5f32.to_le_bytes());
+        payload.extend(2u32.to_le_bytes());
+
+        let saved = blob(1, &payload);
+        let state = GainPluginState::read(&mut saved.as_slice()).unwrap();
+
+        assert_eq!(
+            state,
+            GainPluginState {
+                factor: 1.5,
+                pan: 0.0,
+                delay_time: DEFAULT_DELAY_TIME,
+                feedback: DEFAULT_FEEDBACK,
+                mix: DEFAULT_MIX,
+                port_config: Some(PortConfig::Stereo),
+            }
+        );
+    }
+
+    #[test]
+    fn version_2_is_migrated() {
+        let mut payload = Vec::new();
+        payload.extend(0.5f32.to_le_bytes());
+        payload.extend(1u32.to_le_bytes());
+        payload.extend(0.25f32.to_le_bytes());
+
+        let saved = blob(2, &payload);
+        let state = GainPluginState::read(&mut saved.as_slice()).unwrap();
+
+        assert_eq!(
+            state,
+            GainPluginState {
+                factor: 0.5,
+                pan: 0.25,
+                delay_time: DEFAULT_DELAY_TIME,
+                feedback: DEFAULT_FEEDBACK,
+                mix: DEFAULT_MIX,
+                port_config: Some(PortConfig::Mono),
+            }
+        );
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let saved = blob(STATE_VERSION + 1, &[0; 24]);
+        assert!(GainPluginState::read(&mut saved.as_slice()).is_err());
+    }
+
+    #[test]
+    fn truncated_payload_is_rejected() {
+        let saved = blob(STATE_VERSION, &[0; 20]);
+        assert!(GainPluginState::read(&mut saved.as_slice()).is_err());
+
+        // the payload is shorter than its header says.
+        let mut saved = blob(STATE_VERSION, &[0; 24]);
+        saved.truncate(22);
+        assert!(GainPluginState::read(&mut saved.as_slice()).is_err());
+    }
+
+    #[test]
+    fn oversized_payload_is_rejected_before_reading_it() {
+        let mut saved = Vec::new();
+        saved.extend(STATE_VERSION.to_le_bytes());
+        saved.extend(u32::MAX.to_le_bytes());
+
+        assert!(GainPluginState::read(&mut saved.as_slice()).is_err());
+    }
+}
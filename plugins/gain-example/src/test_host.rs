@@ -0,0 +1,294 @@
+//! This module implements a minimal CLAP host for testing the plugins in this crate,
+//! without having to build them with clap-wrapper and load them into a DAW.
+//!
+//! It loads the plugins through the same `rust_clap_entry` that clap-wrapper links against,
+//! so everything a real host would call is exercised: the entry, the factory,
+//! and the plugin instance with its audio processor.
+//! The host itself implements no extensions, just like a very basic real host.
+
+use crate::rust_clap_entry;
+use clack_host::events::event_types::ParamValueEvent;
+use clack_host::events::Pckn;
+use clack_host::prelude::*;
+use clack_host::utils::Cookie;
+use std::ffi::{CStr, CString};
+
+pub const SAMPLE_RATE: f64 = 48_000.0;
+
+/// The number of frames processed in a single block.
+pub const BLOCK_SIZE: usize = 32;
+
+/// A block of stereo audio, one array per channel.
+pub type Block = [[f32; BLOCK_SIZE]; 2];
+
+pub struct TestHost;
+
+pub struct TestHostShared;
+
+pub struct TestHostMainThread;
+
+pub struct TestHostAudioProcessor;
+
+impl HostHandlers for TestHost {
+    type Shared<'a> = TestHostShared;
+    type MainThread<'a> = TestHostMainThread;
+    type AudioProcessor<'a> = TestHostAudioProcessor;
+}
+
+// the requests are ignored, as each test only runs a fixed sequence of calls.
+impl<'a> SharedHandler<'a> for TestHostShared {
+    fn request_restart(&self) {}
+    fn request_process(&self) {}
+    fn request_callback(&self) {}
+}
+
+impl<'a> MainThreadHandler<'a> for TestHostMainThread {}
+
+impl<'a> AudioProcessorHandler<'a> for TestHostAudioProcessor {}
+
+/// Loads this crate's CLAP entry, like a host would load the plugin binary.
+pub fn load_entry() -> PluginEntry {
+    // SAFETY: the entry is defined by this crate, and has no bundle on disk.
+    unsafe { PluginEntry::load_from_raw(&rust_clap_entry, "") }.unwrap()
+}
+
+/// The ids of all plugins the entry's factory describes, in order.
+pub fn plugin_ids(entry: &PluginEntry) -> Vec<CString> {
+    let factory = entry.get_plugin_factory().unwrap();
+
+    factory
+        .plugin_descriptors()
+        .map(|descriptor| descriptor.id().unwrap().to_owned())
+        .collect()
+}
+
+/// Creates an instance of the plugin with the given id.
+pub fn create_plugin(
+    entry: &PluginEntry,
+    plugin_id: &CStr,
+) -> Result<PluginInstance<TestHost>, PluginInstanceError> {
+    let host_info = HostInfo::new("Test Host", "free-audio", "", "0.1.0").unwrap();
+
+    PluginInstance::<TestHost>::new(
+        |_| TestHostShared,
+        |_| TestHostMainThread,
+        entry,
+        plugin_id,
+        &host_info,
+    )
+}
+
+/// A plugin instance that is activated and processing until dropped.
+pub struct TestPlugin {
+    instance: PluginInstance<TestHost>,
+    processor: Option<StartedPluginAudioProcessor<TestHost>>,
+}
+
+impl TestPlugin {
+    /// Creates the plugin with the given id, activates it and starts processing.
+    pub fn activate(entry: &PluginEntry, plugin_id: &CStr) -> Self {
+        let mut instance = create_plugin(entry, plugin_id).unwrap();
+
+        let audio_config = PluginAudioConfiguration {
+            sample_rate: SAMPLE_RATE,
+            min_frames_count: BLOCK_SIZE as u32,
+            max_frames_count: BLOCK_SIZE as u32,
+        };
+        let processor = instance
+            .activate(|_, _| TestHostAudioProcessor, audio_config)
+            .unwrap();
+
+        Self {
+            instance,
+            processor: Some(processor.start_processing().unwrap()),
+        }
+    }
+
+    /// Runs a block of stereo audio through the plugin, and returns the output channels.
+    /// The given parameter values are sent as events at the start of the block.
+    /// Without input, the host passes no input channels, as if the input port was deactivated.
+    pub fn process(&mut self, input: Option<Block>, param_values: &[(ClapId, f64)]) -> Block {
+        let mut events = EventBuffer::new();
+        for &(param_id, value) in param_values {
+            events.push(&ParamValueEvent::new(
+                0,
+                param_id,
+                Pckn::match_all(),
+                value,
+                Cookie::empty(),
+            ));
+        }
+
+        let input_channel_count = if input.is_some() { 2 } else { 0 };
+        let mut input_channels = input.unwrap_or([[0.0; BLOCK_SIZE]; 2]);
+        // the output starts out as garbage, which the plugin must overwrite.
+        let mut output_channels = [[f32::NAN; BLOCK_SIZE]; 2];
+
+        let mut input_ports = AudioPorts::with_capacity(2, 1);
+        let mut output_ports = AudioPorts::with_capacity(2, 1);
+
+        let input_buffers = input_ports.with_input_buffers([AudioPortBuffer {
+            latency: 0,
+            channels: AudioPortBufferType::f32_input_only(
+                input_channels
+                    .iter_mut()
+                    .take(input_channel_count)
+                    .map(|channel| InputChannel::variable(channel)),
+            ),
+        }]);
+        let mut output_buffers = output_ports.with_output_buffers([AudioPortBuffer {
+            latency: 0,
+            channels: AudioPortBufferType::f32_output_only(
+                output_channels
+                    .iter_mut()
+                    .map(|channel| channel.as_mut_slice()),
+            ),
+        }]);
+
+        self.processor
+            .as_mut()
+            .unwrap()
+            .process(
+                &input_buffers,
+                &mut output_buffers,
+                &InputEvents::from_buffer(&events),
+                &mut OutputEvents::void(),
+                None,
+                None,
+            )
+            .unwrap();
+
+        output_channels
+    }
+}
+
+impl Drop for TestPlugin {
+    fn drop(&mut self) {
+        if let Some(processor) = self.processor.take() {
+            self.instance.deactivate(processor.stop_processing());
+        }
+    }
+}
+
+mod tests {
+    use super::*;
+    use crate::params::{GAIN_PARAM_ID, MAX_GAIN, PAN_PARAM_ID};
+    use crate::GainPluginFactory;
+    use std::f32::consts::SQRT_2;
+
+    /// Enough blocks for any parameter change to have been fully applied.
+    const SETTLE_BLOCKS: usize = (SAMPLE_RATE / 10.0) as usize / BLOCK_SIZE;
+
+    /// A ramp on the left channel and its negation on the right one.
+    fn test_input() -> Block {
+        let left = std::array::from_fn(|frame| frame as f32 / BLOCK_SIZE as f32);
+        let right = left.map(|sample: f32| -sample);
+        [left, right]
+    }
+
+    /// The default gain factor of the plugin with the given id.
+    fn default_factor(plugin_id: &CStr) -> f32 {
+        GainPluginFactory::new().find_plugin(plugin_id).unwrap().3
+    }
+
+    /// Processes blocks of constant input until any parameter changes have been applied,
+    /// and returns the output of the last one.
+    fn settle(plugin: &mut TestPlugin) -> Block {
+        let mut output = [[0.0; BLOCK_SIZE]; 2];
+        for _ in 0..SETTLE_BLOCKS {
+            output = plugin.process(Some([[1.0; BLOCK_SIZE]; 2]), &[]);
+        }
+        output
+    }
+
+    /// Asserts that each output sample is the input sample multiplied with the given factor.
+    /// The centered pan is only 1 up to rounding errors, so the samples aren't compared exactly.
+    fn assert_scaled(input: Block, output: Block, factor: f32) {
+        for (input, output) in input.iter().flatten().zip(output.iter().flatten()) {
+            let expected = input * factor;
+            assert!(
+                (output - expected).abs() < 1e-6,
+                "expected {expected}, got {output}"
+            );
+        }
+    }
+
+    #[test]
+    fn each_plugin_applies_its_default_gain() {
+        let entry = load_entry();
+
+        for plugin_id in plugin_ids(&entry) {
+            let mut plugin = TestPlugin::activate(&entry, &plugin_id);
+            let input = test_input();
+            let output = plugin.process(Some(input), &[]);
+            assert_scaled(input, output, default_factor(&plugin_id));
+        }
+    }
+
+    #[test]
+    fn gain_changes_are_smoothed() {
+        let entry = load_entry();
+        let plugin_id = plugin_ids(&entry).remove(0);
+        let default_factor = default_factor(&plugin_id);
+        let mut plugin = TestPlugin::activate(&entry, &plugin_id);
+
+        // with a constant input of 1, the output is the gain applied to each frame.
+        let input = [[1.0; BLOCK_SIZE]; 2];
+        let output = plugin.process(Some(input), &[(GAIN_PARAM_ID, MAX_GAIN as f64)]);
+        for channel in output {
+            assert!(channel[0] > default_factor);
+            assert!(channel.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(channel[BLOCK_SIZE - 1] < MAX_GAIN);
+        }
+
+        assert_scaled(input, settle(&mut plugin), MAX_GAIN);
+    }
+
+    #[test]
+    fn pan_moves_the_signal_to_one_side() {
+        let entry = load_entry();
+        let plugin_id = plugin_ids(&entry).remove(0);
+        let default_factor = default_factor(&plugin_id);
+        let mut plugin = TestPlugin::activate(&entry, &plugin_id);
+
+        plugin.process(Some([[1.0; BLOCK_SIZE]; 2]), &[(PAN_PARAM_ID, 1.0)]);
+        let [left, right] = settle(&mut plugin);
+
+        // a hard-panned channel is boosted by 3 dB, see the pan module.
+        for (left, right) in left.into_iter().zip(right) {
+            assert!(left.abs() < 1e-6, "expected silence, got {left}");
+            assert!((right - default_factor * SQRT_2).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn output_is_silenced_without_input() {
+        let entry = load_entry();
+
+        for plugin_id in plugin_ids(&entry) {
+            let mut plugin = TestPlugin::activate(&entry, &plugin_id);
+            assert_eq!(plugin.process(None, &[]), [[0.0; BLOCK_SIZE]; 2]);
+        }
+    }
+
+    #[test]
+    fn creates_each_described_plugin() {
+        let entry = load_entry();
+        let plugin_ids = plugin_ids(&entry);
+        assert!(!plugin_ids.is_empty());
+
+        for plugin_id in plugin_ids {
+            assert!(create_plugin(&entry, &plugin_id).is_ok());
+        }
+    }
+
+    #[test]
+    fn unknown_plugin_id_is_rejected() {
+        let entry = load_entry();
+        let known_id = plugin_ids(&entry).remove(0);
+        let unknown_id = CString::new(format!("{}.unknown", known_id.to_str().unwrap())).unwrap();
+
+        assert!(create_plugin(&entry, &unknown_id).is_err());
+        assert!(create_plugin(&entry, c"").is_err());
+    }
+}
@@ -0,0 +1,45 @@
+//! This module provides debug assertions that verify
+//! CLAP callbacks are invoked on the thread we expect them on.
+//!
+//! Touching main-thread state from the audio thread (or vice versa)
+//! is one of the most common causes of crashes in real plugins.
+//! The host's thread-check extension lets us catch such mistakes early.
+
+use clack_extensions::thread_check::HostThreadCheck;
+use clack_plugin::prelude::*;
+
+/// Asks the host which thread the current callback runs on.
+/// All checks are skipped in release builds,
+/// or if the host doesn't implement the thread-check extension.
+#[derive(Copy, Clone)]
+pub struct ThreadChecker(Option<HostThreadCheck>);
+
+impl ThreadChecker {
+    pub fn new(host: &HostSharedHandle) -> Self {
+        Self(host.get_extension())
+    }
+
+    /// Panics in debug builds if the host reports that we're not on the main thread.
+    #[inline]
+    pub fn debug_assert_main_thread(&self, host: &HostSharedHandle) {
+        if let Some(thread_check) = &self.0 {
+            debug_assert_ne!(
+                thread_check.is_main_thread(host),
+                Some(false),
+                "expected to be called on the main thread"
+            );
+        }
+    }
+
+    /// Panics in debug builds if the host reports that we're not on the audio thread.
+    #[inline]
+    pub fn debug_assert_audio_thread(&self, host: &HostSharedHandle) {
+        if let Some(thread_check) = &self.0 {
+            debug_assert_ne!(
+                thread_check.is_audio_thread(host),
+                Some(false),
+                "expected to be called on the audio thread"
+            );
+        }
+    }
+}
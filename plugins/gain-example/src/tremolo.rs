@@ -0,0 +1,66 @@
+//! This module implements a tempo-synced tremolo,
+//! which is applied to the gain if the "tremolo" feature is enabled.
+//!
+//! It demonstrates how to read the host's transport (tempo, playhead and play state),
+//! which the host passes along with every process call.
+
+use clack_plugin::events::event_types::{TransportEvent, TransportFlags};
+use std::f64::consts::TAU;
+
+/// How many times the gain dips per beat, i.e. eighth notes.
+const CYCLES_PER_BEAT: f64 = 2.0;
+
+/// How far the gain dips, relative to the gain parameter.
+const DEPTH: f32 = 0.2;
+
+/// The tempo to use if the host doesn't provide one, in beats per minute.
+const DEFAULT_TEMPO: f64 = 120.0;
+
+pub struct Tremolo {
+    sample_rate: f64,
+
+    /// The position in beats where the current block starts.
+    beat_position: f64,
+}
+
+impl Tremolo {
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            sample_rate,
+            beat_position: 0.0,
+        }
+    }
+
+    /// Applies the tremolo to the gain of every frame in the current block,
+    /// then moves on to the next block.
+    ///
+    /// The transport describes the state of the host's timeline at the start of the block.
+    /// It is absent if the host doesn't have a timeline, e.g. in a live setup.
+    pub fn apply(&mut self, transport: Option<&TransportEvent>, gains: &mut [f32]) {
+        let tempo = transport
+            .filter(|transport| transport.flags.contains(TransportFlags::HAS_TEMPO))
+            .map_or(DEFAULT_TEMPO, |transport| transport.tempo);
+
+        // while the host is playing, follow its playhead,
+        // so that the tremolo stays on the beat even after the user jumped or the host looped.
+        // otherwise, keep running freely from where we were.
+        if let Some(transport) = transport {
+            let flags = TransportFlags::IS_PLAYING | TransportFlags::HAS_BEATS_TIMELINE;
+            if transport.flags.contains(flags) {
+                self.beat_position = transport.song_pos_beats.to_float();
+            }
+        }
+
+        // tempo changes within the block (see tempo_inc) are too small to matter here.
+        let beats_per_frame = tempo / 60.0 / self.sample_rate;
+
+        for (frame, gain) in gains.iter_mut().enumerate() {
+            let cycle = (self.beat_position + frame as f64 * beats_per_frame) * CYCLES_PER_BEAT;
+            // a raised cosine, so that the dip starts on the beat.
+            let dip = 0.5 - 0.5 * (cycle * TAU).cos();
+            *gain *= 1.0 - DEPTH * dip as f32;
+        }
+
+        self.beat_position += gains.len() as f64 * beats_per_frame;
+    }
+}
@@ -0,0 +1,147 @@
+//! The errors that can occur while running an xtask command.
+
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum XtaskError {
+    /// Reading or writing files, or launching a tool, failed
+    Io(std::io::Error),
+
+    /// An external tool required for the build is not installed
+    ToolNotFound {
+        tool: &'static str,
+        install_hint: &'static str,
+    },
+
+    /// `rustup target add` failed for the given target(s)
+    TargetAddFailed(String),
+
+    /// `cargo metadata` failed or printed something we couldn't parse
+    CargoMetadataFailed,
+
+    /// The workspace doesn't contain a package with the given name
+    PackageNotFound(String),
+
+    /// `cargo build` failed, optionally for a specific target triple
+    CargoBuildFailed { target: Option<String> },
+
+    /// Cargo didn't produce a static library for the given crate
+    StaticLibNotFound(String),
+
+    /// The given static library doesn't export the `rust_clap_entry` symbol
+    EntrySymbolMissing(PathBuf),
+
+    /// Combining the static libraries of both macOS architectures failed
+    LipoFailed,
+
+    /// The CMake project in the given directory is incomplete
+    CmakeFilesMissing(PathBuf),
+
+    /// Configuring the CMake project failed
+    CmakeConfigureFailed,
+
+    /// Building the plugins with CMake failed
+    CmakeBuildFailed,
+
+    /// The CMake output directory didn't contain any plugins
+    NoPluginsFound(PathBuf),
+
+    /// A macOS bundle doesn't have the layout hosts expect
+    InvalidBundle(String),
+
+    /// Stripping debug symbols from the given binary failed
+    StripFailed(PathBuf),
+
+    /// Re-signing the given bundle failed
+    CodesignFailed(PathBuf),
+
+    /// Writing to the given plugin install directory was denied
+    InstallPermissionDenied(PathBuf),
+
+    /// The name passed to `xtask new` is not a valid crate name
+    InvalidCrateName(String),
+
+    /// The crate `xtask new` should create already exists
+    CrateExists(PathBuf),
+}
+
+impl fmt::Display for XtaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XtaskError::Io(e) => write!(f, "{}", e),
+            XtaskError::ToolNotFound { tool, install_hint } => {
+                write!(
+                    f,
+                    "Required tool '{}' was not found. {}",
+                    tool, install_hint
+                )
+            }
+            XtaskError::TargetAddFailed(target) => write!(f, "Failed to add target {}", target),
+            XtaskError::CargoMetadataFailed => write!(f, "Failed to read the workspace metadata"),
+            XtaskError::PackageNotFound(crate_name) => {
+                write!(f, "The workspace has no crate named '{}'", crate_name)
+            }
+            XtaskError::CargoBuildFailed { target: None } => {
+                write!(f, "Failed to build static library")
+            }
+            XtaskError::CargoBuildFailed {
+                target: Some(target),
+            } => write!(f, "Failed to build for {}", target),
+            XtaskError::StaticLibNotFound(crate_name) => write!(
+                f,
+                "No static library was built for crate '{}'. \
+                 Make sure its [lib] crate-type includes \"staticlib\".",
+                crate_name
+            ),
+            XtaskError::EntrySymbolMissing(path) => write!(
+                f,
+                "The static library {} doesn't export the 'rust_clap_entry' symbol. \
+                 Make sure the crate declares `#[unsafe(no_mangle)] pub static rust_clap_entry`.",
+                path.display()
+            ),
+            XtaskError::LipoFailed => write!(f, "Failed to create universal binary with lipo"),
+            XtaskError::CmakeFilesMissing(dir) => {
+                write!(f, "Required CMake files not found in {}", dir.display())
+            }
+            XtaskError::CmakeConfigureFailed => write!(f, "CMake configuration failed"),
+            XtaskError::CmakeBuildFailed => write!(f, "Plugin build failed"),
+            XtaskError::NoPluginsFound(dir) => write!(
+                f,
+                "No plugins were found in the CMake output directory {}",
+                dir.display()
+            ),
+            XtaskError::InvalidBundle(problem) => write!(f, "{}", problem),
+            XtaskError::StripFailed(path) => write!(f, "Failed to strip {}", path.display()),
+            XtaskError::CodesignFailed(path) => write!(f, "Failed to re-sign {}", path.display()),
+            XtaskError::InstallPermissionDenied(dir) => write!(
+                f,
+                "Permission denied installing to {}. \
+                 Run the build from an elevated (administrator) prompt, \
+                 or use --install-user to install for the current user only.",
+                dir.display()
+            ),
+            XtaskError::InvalidCrateName(name) => write!(
+                f,
+                "Invalid crate name '{}': use lowercase letters, digits and dashes only",
+                name
+            ),
+            XtaskError::CrateExists(dir) => write!(f, "{} already exists", dir.display()),
+        }
+    }
+}
+
+impl std::error::Error for XtaskError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            XtaskError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for XtaskError {
+    fn from(e: std::io::Error) -> Self {
+        XtaskError::Io(e)
+    }
+}
@@ -1,7 +1,28 @@
-use clap::{Parser, Subcommand};
+mod error;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use error::XtaskError;
 use std::fs;
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, ExitCode, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The macOS version the plugins run on unless --macos-min-version is given,
+/// matching the default of the CMake script
+const DEFAULT_MACOS_MIN_VERSION: &str = "15.4";
+
+/// Whether informational output should be suppressed.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Prints an informational message, unless `--quiet` was passed.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
 
 #[derive(Debug, Parser)]
 #[command(
@@ -9,6 +30,14 @@ use std::process::Command;
     about = "Build CLAP-first audio plugins from a Rust crate"
 )]
 struct Cli {
+    /// Only print warnings, errors and the final result
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Pass --verbose to cargo
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -16,119 +45,225 @@ struct Cli {
 #[derive(Debug, Subcommand)]
 enum Commands {
     /// Build a crate as a CLAP plugin
-    Build {
-        /// The crate to build as a static library
-        crate_name: String,
-
-        /// Release mode (default is debug)
-        #[arg(long)]
-        release: bool,
-
-        /// Plugin bundle identifier
-        #[arg(long, default_value = "org.free-audio.rust-gain-example")]
-        bundle_id: String,
-
-        /// Clean build directories first
-        #[arg(long)]
-        clean: bool,
-
-        /// Install the resulting plugins to the local drive.
-        /// Not supported on Windows.
-        #[arg(long)]
-        install: bool,
+    Build(BuildArgs),
+
+    /// Create a new plugin crate in plugins/ based on the gain example
+    New {
+        /// The name of the new crate, e.g. "my-plugin"
+        name: String,
     },
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+#[derive(Debug, Args)]
+struct BuildArgs {
+    /// The crate to build as a static library
+    crate_name: String,
 
-    match cli.command {
-        Commands::Build {
-            crate_name,
-            release,
-            bundle_id,
-            clean,
-            install,
-        } => build_plugin(crate_name, release, bundle_id, clean, install)?,
+    /// Release mode (default is debug)
+    #[arg(long)]
+    release: bool,
+
+    /// Plugin bundle identifier (default is "org.free-audio.<CRATE_NAME>")
+    #[arg(long)]
+    bundle_id: Option<String>,
+
+    /// Clean build directories first
+    #[arg(long)]
+    clean: bool,
+
+    /// Install the resulting plugins to the system-wide plugin directories
+    #[arg(long)]
+    install: bool,
+
+    /// Copy the resulting plugins to the current user's plugin directories.
+    /// Unlike --install, this doesn't require write access to system directories.
+    #[arg(long, conflicts_with = "install")]
+    install_user: bool,
+
+    /// Directory to place the final plugins in
+    /// (default is target/<profile>/plugins).
+    /// Relative paths are resolved against the current directory.
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+
+    /// Also build a standalone application hosting the plugin with the given CLAP id.
+    /// Can be passed multiple times to build a standalone for several plugins.
+    #[arg(long = "standalone", value_name = "PLUGIN_ID")]
+    standalone_plugin_ids: Vec<String>,
+
+    /// Minimum macOS version the plugins should run on, e.g. "11.0".
+    /// Both the static library and the CMake build target this version.
+    /// Only used when building on macOS.
+    #[arg(long, value_name = "VERSION", default_value = DEFAULT_MACOS_MIN_VERSION)]
+    macos_min_version: String,
+
+    /// The clap-wrapper tag, branch or commit to build against (default is "main").
+    /// Pin this for reproducible builds.
+    #[arg(long, value_name = "REF", env = "CLAP_WRAPPER_REF")]
+    clap_wrapper_ref: Option<String>,
+
+    /// Architectures to build for on macOS.
+    /// Building for a single architecture skips lipo, which is faster for local testing.
+    #[arg(long, value_enum, default_value_t = MacosArch::Universal)]
+    arch: MacosArch,
+
+    /// Strip debug symbols from the plugin binaries (release builds only)
+    #[arg(long, requires = "release")]
+    strip: bool,
+
+    /// Run CMake even if neither the static library nor the CMake inputs changed
+    #[arg(long)]
+    force: bool,
+
+    /// Pass an additional definition to CMake, e.g. `-D CMAKE_CXX_COMPILER_LAUNCHER=ccache`.
+    /// Can be passed multiple times, and overrides the definitions made by xtask,
+    /// except for those that determine the names and location of the built plugins.
+    #[arg(short = 'D', long = "cmake-define", value_name = "KEY=VALUE", value_parser = parse_cmake_define)]
+    cmake_defines: Vec<String>,
+
+    /// Open the output directory in the file manager after a successful build
+    #[arg(long)]
+    reveal: bool,
+}
+
+/// The architectures a macOS build contains
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum MacosArch {
+    /// Both Intel and Apple Silicon, combined with lipo
+    Universal,
+    /// Apple Silicon only
+    Arm64,
+    /// Intel only
+    #[value(name = "x86_64")]
+    X86_64,
+}
+
+impl MacosArch {
+    /// The architecture names used by lipo and CMake
+    fn architectures(self) -> &'static [&'static str] {
+        match self {
+            MacosArch::Universal => &["x86_64", "arm64"],
+            MacosArch::Arm64 => &["arm64"],
+            MacosArch::X86_64 => &["x86_64"],
+        }
     }
 
-    Ok(())
+    /// The Rust target to build for, unless building a universal binary
+    fn target(self) -> Option<&'static str> {
+        match self {
+            MacosArch::Universal => None,
+            MacosArch::Arm64 => Some("aarch64-apple-darwin"),
+            MacosArch::X86_64 => Some("x86_64-apple-darwin"),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+
+    let cargo_verbosity = if cli.quiet {
+        Some("--quiet")
+    } else if cli.verbose {
+        Some("--verbose")
+    } else {
+        None
+    };
+
+    let result = match cli.command {
+        Commands::Build(args) => build_plugin(args, cargo_verbosity),
+        Commands::New { name } => new_plugin(&name),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
 
 /// Build a plugin from a Rust crate
-fn build_plugin(
-    crate_name: String,
-    release: bool,
-    bundle_id: String,
-    clean: bool,
-    install: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
+fn build_plugin(args: BuildArgs, cargo_verbosity: Option<&str>) -> Result<(), XtaskError> {
+    let BuildArgs {
+        crate_name,
+        release,
+        bundle_id,
+        clean,
+        install,
+        install_user,
+        out_dir,
+        standalone_plugin_ids,
+        macos_min_version,
+        clap_wrapper_ref,
+        arch,
+        strip,
+        force,
+        cmake_defines,
+        reveal,
+    } = args;
+
+    // Fail early with a helpful message if a required tool is missing
+    check_required_tools()?;
+
     // Get the project root directory
     let project_root = project_root();
 
     // Clean if requested
     if clean {
-        println!("Cleaning build directories...");
+        info!("Cleaning build directories...");
         let _ = fs::remove_dir_all(project_root.join("target/cmake-build"));
         let _ = fs::remove_dir_all(project_root.join("target/cmake-assets"));
         let _ = fs::remove_dir_all(project_root.join("target/plugins"));
     }
 
-    // Normalize crate name for file naming
-    let normalized_crate_name = crate_name.replace('-', "_");
+    // Derive the bundle identifier from the crate name unless one was given,
+    // so that different plugin crates don't end up sharing an identifier
+    let bundle_id = bundle_id.unwrap_or_else(|| format!("org.free-audio.{}", crate_name));
 
     // Determine the output directory based on build profile
     let profile = if release { "release" } else { "debug" };
 
     let static_lib_file = if cfg!(target_os = "macos") {
-        // on macOS, build for both architectures
-        // and create a universal binary using lipo
-        build_universal_macos_binary(&project_root, &crate_name, &normalized_crate_name, release)?
+        match arch.target() {
+            // by default, build for both architectures
+            // and create a universal binary using lipo
+            None => build_universal_macos_binary(
+                &project_root,
+                &crate_name,
+                release,
+                Some(&macos_min_version),
+                cargo_verbosity,
+            )?,
+            // a single architecture doesn't need lipo
+            Some(target) => build_single_arch_macos_binary(
+                &project_root,
+                &crate_name,
+                release,
+                target,
+                Some(&macos_min_version),
+                cargo_verbosity,
+            )?,
+        }
     } else {
         // Regular build for the current architecture
-        println!("Building static library for crate '{}'...", crate_name);
-
-        let mut cargo_args = vec!["build"];
-
-        // Configure build profile
-        if release {
-            cargo_args.push("--release");
-        }
-
-        // Add the crate to build
-        cargo_args.push("-p");
-        cargo_args.push(&crate_name);
-
-        let status = Command::new("cargo")
-            .args(&cargo_args)
-            .current_dir(&project_root)
-            .status()?;
+        info!("Building static library for crate '{}'...", crate_name);
 
-        if !status.success() {
-            return Err("Failed to build static library".into());
-        }
-
-        let target_dir = project_root.join("target").join(profile);
-
-        // Determine the static library name based on the platform
-        if cfg!(windows) {
-            // On Windows, the static library is named: crate_name.lib
-            target_dir.join(format!("{}.lib", normalized_crate_name))
-        } else {
-            // On Unix-like systems (Linux, macOS), the static library is named: libcrate_name.a
-            target_dir.join(format!("lib{}.a", normalized_crate_name))
-        }
+        cargo_build(
+            &project_root,
+            &crate_name,
+            release,
+            None,
+            None,
+            cargo_verbosity,
+        )?
     };
 
-    if !static_lib_file.exists() {
-        return Err(format!(
-            "Static library file not found: {}",
-            static_lib_file.display()
-        )
-        .into());
-    }
+    info!("Found static library: {}", static_lib_file.display());
 
-    println!("Found static library: {}", static_lib_file.display());
+    // A missing entry only shows up as a cryptic linker error in CMake otherwise
+    verify_entry_symbol(&static_lib_file)?;
 
     // Create the CMake build directory
     let cmake_build_dir = project_root.join("target/cmake-build");
@@ -142,21 +277,20 @@ fn build_plugin(
 
     // Check if the required files exist
     if !build_cmake.exists() || !clap_entry_cpp.exists() || !clap_entry_h.exists() {
-        return Err("Required CMake files not found in xtask/cmake directory".into());
+        return Err(XtaskError::CmakeFilesMissing(cmake_dir));
     }
 
-
     // Create a temporary assets directory for CMake output
     let cmake_assets_dir = project_root.join("target/cmake-assets");
     fs::create_dir_all(&cmake_assets_dir)?;
 
     // Final plugin output directory
-    let plugin_output_dir = project_root.join("target").join(profile).join("plugins");
+    let plugin_output_dir = match out_dir {
+        Some(out_dir) => std::path::absolute(out_dir)?,
+        None => project_root.join("target").join(profile).join("plugins"),
+    };
     fs::create_dir_all(&plugin_output_dir)?;
 
-    // Run CMake to configure the build
-    println!("Configuring CMake build...");
-
     let mut cmake_args = vec![
         "-S".to_string(),
         cmake_dir.display().to_string(),
@@ -168,20 +302,261 @@ fn build_plugin(
         format!("-DPLUGIN_OUTPUT_DIR={}", cmake_assets_dir.display()),
         format!(
             "-DINSTALL_PLUGINS_AFTER_BUILD={}",
-            if install { "ON" } else { "OFF" }
+            // on Windows, the plugins are copied by xtask itself after the build
+            if install && !cfg!(windows) {
+                "ON"
+            } else {
+                "OFF"
+            }
+        ),
+        format!(
+            "-DSTANDALONE_CONFIGURATIONS={}",
+            standalone_configurations(&crate_name, &standalone_plugin_ids)
+        ),
+        // always passed, so that CMake doesn't keep a cached ref from a previous build
+        format!(
+            "-DCLAP_WRAPPER_REF={}",
+            clap_wrapper_ref.unwrap_or_default()
         ),
+        // the same version the static library was built for,
+        // as CMake would otherwise fall back to its own default
+        format!("-DCMAKE_OSX_DEPLOYMENT_TARGET={}", macos_min_version),
     ];
 
-    let status = Command::new("cmake")
-        .args(&cmake_args)
-        .status()?;
+    if cfg!(target_os = "macos") {
+        cmake_args.push(format!(
+            "-DCMAKE_OSX_ARCHITECTURES={}",
+            arch.architectures().join(";")
+        ));
+    }
+
+    // Use the crate's own Info.plist template if it has one.
+    // An empty value is passed otherwise, so that CMake doesn't keep a cached template
+    // from a previous build of another crate.
+    let info_plist_template = project_root
+        .join("plugins")
+        .join(&crate_name)
+        .join("Info.plist.in");
+    let info_plist_template = info_plist_template.exists().then_some(info_plist_template);
+
+    if let Some(info_plist_template) = &info_plist_template {
+        info!(
+            "Using Info.plist template: {}",
+            info_plist_template.display()
+        );
+    }
+
+    cmake_args.push(format!(
+        "-DINFO_PLIST_TEMPLATE={}",
+        info_plist_template
+            .as_deref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default()
+    ));
+
+    // CMake uses the last definition of a variable,
+    // so user definitions are passed last to be able to override ours
+    cmake_args.extend(cmake_defines.iter().map(|define| format!("-D{}", define)));
+
+    // Skip CMake entirely if nothing changed since the last successful build
+    let stamp_file = cmake_build_dir.join("xtask-build.stamp");
+    let mut fingerprint_inputs = vec![
+        static_lib_file.as_path(),
+        &build_cmake,
+        &clap_entry_cpp,
+        &clap_entry_h,
+    ];
+    fingerprint_inputs.extend(info_plist_template.as_deref());
+    let fingerprint = build_fingerprint(&cmake_args, profile, &fingerprint_inputs)?;
+
+    if !force && fs::read_to_string(&stamp_file).is_ok_and(|stamp| stamp == fingerprint) {
+        info!("Static library and CMake inputs are unchanged, skipping CMake (use --force to rebuild)");
+    } else {
+        let _ = fs::remove_file(&stamp_file);
+        run_cmake(&cmake_args, &cmake_build_dir, release)?;
+        fs::write(&stamp_file, fingerprint)?;
+    }
+
+    // Copy the plugin files from the CMake output directory to the final plugin directory.
+    // The CMake output directory is shared by all crates and kept between builds,
+    // so only the files named after this crate's outputs are copied.
+    info!("Copying plugin files to final destination...");
+    let output_names = output_names(&crate_name, &standalone_plugin_ids);
+    let plugins = copy_plugin_files(
+        &cmake_assets_dir,
+        &plugin_output_dir,
+        &profile,
+        &output_names,
+    )?;
+
+    if strip {
+        info!("Stripping debug symbols...");
+        strip_plugins(&plugins)?;
+    }
+
+    if cfg!(target_os = "macos") {
+        info!("Verifying plugin bundles...");
+        verify_macos_bundles(&plugins, arch.architectures())?;
+    }
+
+    if install_user {
+        info!("Installing plugins to user plugin directories...");
+        install_plugins(&plugins, InstallScope::User)?;
+    } else if install && cfg!(windows) {
+        info!("Installing plugins to system plugin directories...");
+        install_plugins(&plugins, InstallScope::System)?;
+    }
+
+    info!("Build completed successfully!");
+    println!("Plugins are available in: {}", plugin_output_dir.display());
+
+    if reveal {
+        reveal_in_file_manager(&plugin_output_dir);
+    }
+
+    Ok(())
+}
+
+/// Check that the static library exports the CLAP entry under the name clap_entry.cpp expects.
+/// The check is skipped if the platform's symbol listing tool isn't available.
+fn verify_entry_symbol(static_lib_file: &Path) -> Result<(), XtaskError> {
+    // dumpbin is only available from a Visual Studio developer prompt
+    let output = if cfg!(windows) {
+        Command::new("dumpbin")
+            .arg("/SYMBOLS")
+            .arg(static_lib_file)
+            .output()
+    } else {
+        // only list external symbols
+        Command::new("nm").arg("-g").arg(static_lib_file).output()
+    };
+
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        _ => {
+            info!("Could not list the symbols of the static library, skipping the entry check");
+            return Ok(());
+        }
+    };
+
+    let symbols = String::from_utf8_lossy(&output.stdout);
+    let exported = symbols.lines().any(|line| {
+        if cfg!(windows) {
+            // e.g. "01A 00000000 SECT5  notype       External     | rust_clap_entry"
+            line.contains("External")
+                && !line.contains("UNDEF")
+                && (line.ends_with("| rust_clap_entry") || line.ends_with("| _rust_clap_entry"))
+        } else {
+            // e.g. "0000000000000000 D rust_clap_entry", with a leading underscore on macOS.
+            // undefined symbols, i.e. references to the entry, are listed with type U
+            let mut fields = line.split_whitespace().rev();
+            matches!(
+                (fields.next(), fields.next()),
+                (Some("rust_clap_entry" | "_rust_clap_entry"), Some(kind)) if kind != "U"
+            )
+        }
+    });
+
+    if !exported {
+        return Err(XtaskError::EntrySymbolMissing(
+            static_lib_file.to_path_buf(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// CMake definitions that determine the names and location of the built plugins.
+/// xtask only picks up the outputs it expects after the build, so these can't be overridden.
+const RESERVED_CMAKE_DEFINES: &[(&str, &str)] = &[
+    (
+        "PROJECT_NAME",
+        "the plugins are always named after the crate",
+    ),
+    ("PLUGIN_OUTPUT_DIR", "use --out-dir instead"),
+    ("STANDALONE_CONFIGURATIONS", "use --standalone instead"),
+];
+
+/// Check that a CMake definition passed on the command line has the form KEY=VALUE,
+/// and doesn't override one of the definitions xtask relies on
+fn parse_cmake_define(define: &str) -> Result<String, String> {
+    let key = match define.split_once('=') {
+        Some((key, _)) if !key.is_empty() => key,
+        _ => return Err(format!("expected KEY=VALUE, got '{}'", define)),
+    };
+
+    // The key may carry a type, e.g. PROJECT_NAME:STRING
+    let name = key.split_once(':').map_or(key, |(name, _)| name);
+    match RESERVED_CMAKE_DEFINES
+        .iter()
+        .find(|(reserved, _)| *reserved == name)
+    {
+        Some((_, hint)) => Err(format!("{} can't be overridden, {}", name, hint)),
+        None => Ok(define.to_string()),
+    }
+}
+
+/// Build the list passed to clap-wrapper's STANDALONE_CONFIGURATIONS.
+/// It consists of a (target postfix, output name, plugin id) triple per standalone application.
+fn standalone_configurations(crate_name: &str, plugin_ids: &[String]) -> String {
+    plugin_ids
+        .iter()
+        .map(|plugin_id| {
+            let output_name = standalone_output_name(crate_name, plugin_id);
+            let suffix = plugin_id_suffix(plugin_id);
+            format!("standalone-{suffix};{output_name};{plugin_id}")
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// The last segment of a plugin id, e.g. "halver" for "free-audio.clap.rust-gain-example.halver"
+fn plugin_id_suffix(plugin_id: &str) -> &str {
+    plugin_id.rsplit('.').next().unwrap_or(plugin_id)
+}
+
+/// The name of the standalone application for a plugin,
+/// e.g. "gain-example-halver" for "free-audio.clap.rust-gain-example.halver"
+fn standalone_output_name(crate_name: &str, plugin_id: &str) -> String {
+    format!("{}-{}", crate_name, plugin_id_suffix(plugin_id))
+}
+
+/// The names (without extension) of everything CMake builds for a crate:
+/// the plugin bundles are named after the crate, plus one standalone application per plugin id
+fn output_names(crate_name: &str, standalone_plugin_ids: &[String]) -> Vec<String> {
+    let mut names = vec![crate_name.to_string()];
+    names.extend(
+        standalone_plugin_ids
+            .iter()
+            .map(|plugin_id| standalone_output_name(crate_name, plugin_id)),
+    );
+    names
+}
+
+/// Whether a file or bundle is one of the given outputs, judging by its name
+fn is_build_output(path: &Path, output_names: &[String]) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| output_names.iter().any(|name| name == stem))
+}
+
+/// Configure and build the plugins with CMake
+fn run_cmake(
+    cmake_args: &[String],
+    cmake_build_dir: &Path,
+    release: bool,
+) -> Result<(), XtaskError> {
+    // Run CMake to configure the build
+    info!("Configuring CMake build...");
+
+    let status = Command::new("cmake").args(cmake_args).status()?;
 
     if !status.success() {
-        return Err("CMake configuration failed".into());
+        return Err(XtaskError::CmakeConfigureFailed);
     }
 
     // Build the plugins
-    println!("Building plugins...");
+    info!("Building plugins...");
     let status = Command::new("cmake")
         .arg("--build")
         .arg(cmake_build_dir.to_str().unwrap())
@@ -190,15 +565,150 @@ fn build_plugin(
         .status()?;
 
     if !status.success() {
-        return Err("Plugin build failed".into());
+        return Err(XtaskError::CmakeBuildFailed);
     }
 
-    // Copy the plugin files from the CMake output directory to the final plugin directory
-    println!("Copying plugin files to final destination...");
-    copy_plugin_files(&cmake_assets_dir, &plugin_output_dir, &profile)?;
+    Ok(())
+}
 
-    println!("Build completed successfully!");
-    println!("Plugins are available in: {}", plugin_output_dir.display());
+/// Describe the inputs of the CMake build,
+/// i.e. the arguments passed to CMake and a hash of the contents of all input files.
+/// If this doesn't change between two builds, CMake has nothing to do.
+///
+/// Contents are hashed instead of comparing modification times,
+/// as some inputs are rewritten on every build without changing,
+/// e.g. the universal static library created by lipo on macOS.
+fn build_fingerprint(
+    cmake_args: &[String],
+    profile: &str,
+    inputs: &[&Path],
+) -> Result<String, XtaskError> {
+    use std::hash::{Hash, Hasher};
+
+    let mut fingerprint = format!("{}\n{}\n", profile, cmake_args.join("\n"));
+
+    for input in inputs {
+        // the hash only needs to be stable between runs of the same xtask binary,
+        // a different Rust version merely causes one more CMake run
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        fs::read(input)?.hash(&mut hasher);
+        fingerprint.push_str(&format!("{}: {:016x}\n", input.display(), hasher.finish()));
+    }
+
+    Ok(fingerprint)
+}
+
+/// The plugin crate that new plugins are created from
+const TEMPLATE_CRATE: &str = "gain-example";
+
+/// The 4-char AU subtypes used by the template crate
+const TEMPLATE_AU_SUBTYPES: [&str; 2] = ["Ghlv", "Gdbl"];
+
+/// Create a new plugin crate by copying the template crate
+/// and replacing its crate name, plugin ids, display names and AU subtypes
+fn new_plugin(name: &str) -> Result<(), XtaskError> {
+    let is_valid_name = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !is_valid_name {
+        return Err(XtaskError::InvalidCrateName(name.to_string()));
+    }
+
+    let project_root = project_root();
+    let template_dir = project_root.join("plugins").join(TEMPLATE_CRATE);
+    let crate_dir = project_root.join("plugins").join(name);
+
+    if crate_dir.exists() {
+        return Err(XtaskError::CrateExists(crate_dir));
+    }
+
+    info!(
+        "Creating {} from {}...",
+        crate_dir.display(),
+        TEMPLATE_CRATE
+    );
+    copy_dir_recursive(&template_dir, &crate_dir)?;
+
+    // "my-plugin" becomes "My Plugin"
+    let display_name = name
+        .split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut replacements = vec![
+        (
+            format!("name = \"{}\"", TEMPLATE_CRATE),
+            format!("name = \"{}\"", name),
+        ),
+        (format!("rust-{}", TEMPLATE_CRATE), name.to_string()),
+        ("\"Gain ".to_string(), format!("\"{} ", display_name)),
+    ];
+    for (index, au_subtype) in TEMPLATE_AU_SUBTYPES.iter().enumerate() {
+        replacements.push((
+            format!("\"{}\"", au_subtype),
+            format!("\"{}\"", generate_au_subtype(name, index)),
+        ));
+    }
+
+    replace_in_files(&crate_dir, &replacements)?;
+
+    // the workspace includes all crates in plugins/, so nothing else needs to be registered
+    println!("Created plugin crate '{}' in {}", name, crate_dir.display());
+    println!("Build it with: cargo xtask build {}", name);
+
+    Ok(())
+}
+
+/// Generate a 4-char AU subtype from the crate name,
+/// so that different plugin crates are unlikely to share one
+fn generate_au_subtype(name: &str, index: usize) -> String {
+    use std::hash::{Hash, Hasher};
+
+    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (name, index).hash(&mut hasher);
+    let mut hash = hasher.finish();
+
+    // start with the (uppercased) first letter of the name to make the code recognizable,
+    // and distinguish the plugins in the crate by the remaining characters
+    let mut subtype = name[..1].to_ascii_uppercase();
+    for _ in 0..3 {
+        subtype.push(CHARS[(hash % CHARS.len() as u64) as usize] as char);
+        hash /= CHARS.len() as u64;
+    }
+
+    subtype
+}
+
+/// Apply text replacements to all files in a directory, recursively
+fn replace_in_files(dir: &Path, replacements: &[(String, String)]) -> Result<(), XtaskError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            replace_in_files(&path, replacements)?;
+            continue;
+        }
+
+        let original = fs::read_to_string(&path)?;
+        let mut contents = original.clone();
+        for (from, to) in replacements {
+            contents = contents.replace(from.as_str(), to);
+        }
+
+        if contents != original {
+            fs::write(&path, contents)?;
+        }
+    }
 
     Ok(())
 }
@@ -207,9 +717,10 @@ fn build_plugin(
 fn build_universal_macos_binary(
     project_root: &Path,
     crate_name: &str,
-    normalized_crate_name: &str,
     release: bool,
-) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    deployment_target: Option<&str>,
+    cargo_verbosity: Option<&str>,
+) -> Result<PathBuf, XtaskError> {
     // Ensure both targets are available
     let status = Command::new("rustup")
         .args(&[
@@ -221,72 +732,42 @@ fn build_universal_macos_binary(
         .status()?;
 
     if !status.success() {
-        return Err("Failed to add required targets".into());
+        return Err(XtaskError::TargetAddFailed(
+            "x86_64-apple-darwin, aarch64-apple-darwin".to_string(),
+        ));
     }
 
-    // Build profile
-    let profile = if release { "release" } else { "debug" };
-
     // Build for x86_64 (Intel)
-    println!("Building for x86_64-apple-darwin...");
-    let mut cargo_args = vec!["build"];
-
-    if release {
-        cargo_args.push("--release");
-    }
-
-    cargo_args.extend(&["--target", "x86_64-apple-darwin", "-p", crate_name]);
-
-    let status = Command::new("cargo")
-        .args(&cargo_args)
-        .current_dir(project_root)
-        .status()?;
-
-    if !status.success() {
-        return Err("Failed to build for x86_64-apple-darwin".into());
-    }
+    info!("Building for x86_64-apple-darwin...");
+    let x86_64_lib = cargo_build(
+        project_root,
+        crate_name,
+        release,
+        Some("x86_64-apple-darwin"),
+        deployment_target,
+        cargo_verbosity,
+    )?;
 
     // Build for arm64 (Apple Silicon)
-    println!("Building for aarch64-apple-darwin...");
-    let mut cargo_args = vec!["build"];
-
-    if release {
-        cargo_args.push("--release");
-    }
-
-    cargo_args.extend(&["--target", "aarch64-apple-darwin", "-p", crate_name]);
-
-    let status = Command::new("cargo")
-        .args(&cargo_args)
-        .current_dir(project_root)
-        .status()?;
-
-    if !status.success() {
-        return Err("Failed to build for aarch64-apple-darwin".into());
-    }
-
-    // Path to the x86_64 and arm64 libraries
-    let x86_64_lib = project_root
-        .join("target")
-        .join("x86_64-apple-darwin")
-        .join(profile)
-        .join(format!("lib{}.a", normalized_crate_name));
-
-    let arm64_lib = project_root
-        .join("target")
-        .join("aarch64-apple-darwin")
-        .join(profile)
-        .join(format!("lib{}.a", normalized_crate_name));
+    info!("Building for aarch64-apple-darwin...");
+    let arm64_lib = cargo_build(
+        project_root,
+        crate_name,
+        release,
+        Some("aarch64-apple-darwin"),
+        deployment_target,
+        cargo_verbosity,
+    )?;
 
     // Create output directory for universal binary
     let universal_dir = project_root.join("target").join("universal");
     fs::create_dir_all(&universal_dir)?;
 
-    // Path for the universal library
-    let universal_lib = universal_dir.join(format!("lib{}.a", normalized_crate_name));
+    // Path for the universal library, named like the per-architecture ones
+    let universal_lib = universal_dir.join(x86_64_lib.file_name().unwrap());
 
     // Use lipo to create universal binary
-    println!(
+    info!(
         "Creating universal binary with lipo: {}",
         universal_lib.display()
     );
@@ -301,7 +782,7 @@ fn build_universal_macos_binary(
         .status()?;
 
     if !status.success() {
-        return Err("Failed to create universal binary with lipo".into());
+        return Err(XtaskError::LipoFailed);
     }
 
     // Verify the universal binary
@@ -311,51 +792,530 @@ fn build_universal_macos_binary(
 
     if output.status.success() {
         let info = String::from_utf8_lossy(&output.stdout);
-        println!("Universal binary info: {}", info.trim());
+        info!("Universal binary info: {}", info.trim());
     }
 
     Ok(universal_lib)
 }
 
-/// Copy plugin files from CMake output to final destination
+/// Build for a single macOS architecture, which doesn't require lipo
+fn build_single_arch_macos_binary(
+    project_root: &Path,
+    crate_name: &str,
+    release: bool,
+    target: &str,
+    deployment_target: Option<&str>,
+    cargo_verbosity: Option<&str>,
+) -> Result<PathBuf, XtaskError> {
+    // Ensure the target is available
+    let status = Command::new("rustup")
+        .args(["target", "add", target])
+        .status()?;
+
+    if !status.success() {
+        return Err(XtaskError::TargetAddFailed(target.to_string()));
+    }
+
+    info!("Building for {}...", target);
+    cargo_build(
+        project_root,
+        crate_name,
+        release,
+        Some(target),
+        deployment_target,
+        cargo_verbosity,
+    )
+}
+
+/// Check that the external tools needed for the build are installed,
+/// and explain how to install them otherwise.
+fn check_required_tools() -> Result<(), XtaskError> {
+    let mut required_tools = vec![(
+        "cmake",
+        if cfg!(target_os = "macos") {
+            "Install it with `brew install cmake` or from https://cmake.org/download/"
+        } else if cfg!(windows) {
+            "Install it with `winget install Kitware.CMake` or from https://cmake.org/download/"
+        } else {
+            "Install it with your package manager, e.g. `sudo apt install cmake`"
+        },
+    )];
+
+    if cfg!(target_os = "macos") {
+        required_tools.push((
+            "lipo",
+            "It is part of the Xcode command line tools: run `xcode-select --install`",
+        ));
+        required_tools.push(("rustup", "Install it from https://rustup.rs"));
+    }
+
+    for (tool, install_hint) in required_tools {
+        // we only care whether the tool can be launched at all,
+        // not whether it is happy with the arguments
+        if let Err(e) = Command::new(tool).arg("--version").output() {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return Err(XtaskError::ToolNotFound { tool, install_hint });
+            }
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Run `cargo build` for a single crate, optionally for a specific target triple.
+/// If given, the macOS deployment target is passed to the build via `MACOSX_DEPLOYMENT_TARGET`.
+/// Returns the path of the static library cargo produced for the crate.
+fn cargo_build(
+    project_root: &Path,
+    crate_name: &str,
+    release: bool,
+    target: Option<&str>,
+    deployment_target: Option<&str>,
+    verbosity: Option<&str>,
+) -> Result<PathBuf, XtaskError> {
+    // cargo reports the artifacts it produced as JSON on stdout,
+    // while compiler output and progress are still rendered to stderr
+    let mut cargo_args = vec!["build", "--message-format=json-render-diagnostics"];
+
+    if release {
+        cargo_args.push("--release");
+    }
+
+    if let Some(target) = target {
+        cargo_args.extend(&["--target", target]);
+    }
+
+    if let Some(verbosity) = verbosity {
+        cargo_args.push(verbosity);
+    }
+
+    cargo_args.extend(&["-p", crate_name]);
+
+    // dependencies are built with every crate type they declare,
+    // so a static library may also come from a dependency
+    let package_id = package_id(project_root, crate_name)?;
+
+    let mut command = Command::new("cargo");
+    command.args(&cargo_args).current_dir(project_root);
+
+    if let Some(deployment_target) = deployment_target {
+        command.env("MACOSX_DEPLOYMENT_TARGET", deployment_target);
+    }
+
+    let mut child = command.stdout(Stdio::piped()).spawn()?;
+
+    // read the messages while cargo is running, so that it never blocks on a full pipe
+    let mut static_lib = None;
+    for message in BufReader::new(child.stdout.take().unwrap()).lines() {
+        if let Some(path) = static_lib_artifact(&message?, &package_id) {
+            static_lib = Some(path);
+        }
+    }
+
+    if !child.wait()?.success() {
+        return Err(XtaskError::CargoBuildFailed {
+            target: target.map(str::to_string),
+        });
+    }
+
+    static_lib.ok_or_else(|| XtaskError::StaticLibNotFound(crate_name.to_string()))
+}
+
+/// Get the id cargo identifies a workspace package by in its JSON messages
+fn package_id(project_root: &Path, crate_name: &str) -> Result<String, XtaskError> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .current_dir(project_root)
+        .stderr(Stdio::inherit())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(XtaskError::CargoMetadataFailed);
+    }
+
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&output.stdout).map_err(|_| XtaskError::CargoMetadataFailed)?;
+
+    metadata["packages"]
+        .as_array()
+        .ok_or(XtaskError::CargoMetadataFailed)?
+        .iter()
+        .find(|package| package["name"] == crate_name)
+        .and_then(|package| package["id"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| XtaskError::PackageNotFound(crate_name.to_string()))
+}
+
+/// Get the path of the static library from a JSON message of cargo,
+/// if the message describes a static library artifact of the given package,
+/// whatever its `[lib] name` is.
+fn static_lib_artifact(message: &str, package_id: &str) -> Option<PathBuf> {
+    let message: serde_json::Value = serde_json::from_str(message).ok()?;
+    if message["reason"] != "compiler-artifact" || message["package_id"] != package_id {
+        return None;
+    }
+
+    let crate_types = message["target"]["crate_types"].as_array()?;
+    if !crate_types
+        .iter()
+        .any(|crate_type| crate_type == "staticlib")
+    {
+        return None;
+    }
+
+    // the static library is listed next to other files, like the .rlib of a "lib" crate type
+    message["filenames"]
+        .as_array()?
+        .iter()
+        .filter_map(|filename| filename.as_str())
+        .map(PathBuf::from)
+        .find(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "a" || ext == "lib")
+        })
+}
+
+/// Copy the plugin files with the given output names from CMake output to final destination.
+/// Returns the paths of the copied files and bundles in the destination directory.
 fn copy_plugin_files(
     source_dir: &Path,
     dest_dir: &Path,
     profile: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+    output_names: &[String],
+) -> Result<Vec<PathBuf>, XtaskError> {
     // Create destination directory if it doesn't exist
     fs::create_dir_all(dest_dir)?;
 
     // Handle platform-specific differences
-    if cfg!(target_os = "windows") {
+    let format_source_dirs = if cfg!(target_os = "windows") {
         // On Windows, we need to handle the nested file structure
-        for format in ["VST3", "CLAP"] {
-            let format_source_dir = source_dir.join(format).join(profile);
-            if format_source_dir.exists() {
-                for entry in fs::read_dir(&format_source_dir)? {
-                    let entry = entry?;
-                    let source_path = entry.path();
-                    if source_path.is_file() {
-                        let dest_path = dest_dir.join(source_path.file_name().unwrap());
-                        fs::copy(&source_path, &dest_path)?;
-                    } else if source_path.is_dir() {
-                        let dest_subdir = dest_dir.join(source_path.file_name().unwrap());
-                        copy_dir_recursive(&source_path, &dest_subdir)?;
-                    }
+        ["VST3", "CLAP", "Standalone"]
+            .iter()
+            .map(|format| source_dir.join(format).join(profile))
+            .collect()
+    } else {
+        // On macOS, files are output directly in the asset output directory.
+        // it's a sensible default for Linux as well
+        vec![source_dir.to_path_buf()]
+    };
+
+    // The plugin files and bundles copied in this call
+    let mut copied = Vec::new();
+
+    for format_source_dir in format_source_dirs {
+        if !format_source_dir.exists() {
+            continue;
+        }
+
+        for entry in fs::read_dir(&format_source_dir)? {
+            let source_path = entry?.path();
+            if !is_build_output(&source_path, output_names) {
+                continue;
+            }
+
+            let dest_path = dest_dir.join(source_path.file_name().unwrap());
+            if source_path.is_dir() {
+                copy_dir_recursive(&source_path, &dest_path)?;
+            } else {
+                fs::copy(&source_path, &dest_path)?;
+            }
+            copied.push(dest_path);
+        }
+    }
+
+    // Nothing to copy means the CMake output layout
+    // didn't match what we expect, which must not go unnoticed
+    if copied.is_empty() {
+        return Err(XtaskError::NoPluginsFound(source_dir.to_path_buf()));
+    }
+
+    info!("Copied {} plugin file(s)", copied.len());
+
+    Ok(copied)
+}
+
+/// Check that every bundle built by this invocation has the layout macOS hosts expect,
+/// i.e. an Info.plist declaring the bundle identifier and executable,
+/// and an executable containing all of the given architectures.
+fn verify_macos_bundles(plugins: &[PathBuf], architectures: &[&str]) -> Result<(), XtaskError> {
+    for bundle in plugins {
+        let is_bundle = bundle.extension().is_some_and(|ext| {
+            ["clap", "vst3", "component", "app"].contains(&ext.to_str().unwrap_or(""))
+        });
+        if !is_bundle {
+            continue;
+        }
+
+        let info_plist = bundle.join("Contents").join("Info.plist");
+        if !info_plist.is_file() {
+            return Err(XtaskError::InvalidBundle(format!(
+                "{} is missing Contents/Info.plist",
+                bundle.display()
+            )));
+        }
+
+        let read_plist_key = |key: &str| -> Result<String, XtaskError> {
+            let output = Command::new("plutil")
+                .args(["-extract", key, "raw", "-o", "-"])
+                .arg(&info_plist)
+                .output()?;
+
+            if !output.status.success() {
+                return Err(XtaskError::InvalidBundle(format!(
+                    "{} doesn't declare {}",
+                    info_plist.display(),
+                    key
+                )));
+            }
+
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        };
+
+        read_plist_key("CFBundleIdentifier")?;
+        let executable_name = read_plist_key("CFBundleExecutable")?;
+
+        let executable = bundle.join("Contents").join("MacOS").join(&executable_name);
+        if !executable.is_file() {
+            return Err(XtaskError::InvalidBundle(format!(
+                "{} declares executable '{}', but {} doesn't exist",
+                bundle.display(),
+                executable_name,
+                executable.display()
+            )));
+        }
+
+        let output = Command::new("lipo")
+            .arg("-info")
+            .arg(&executable)
+            .output()?;
+        let lipo_info = String::from_utf8_lossy(&output.stdout);
+        let found_architectures = lipo_info.rsplit(':').next().unwrap_or("");
+
+        for architecture in architectures {
+            if !found_architectures
+                .split_whitespace()
+                .any(|a| a == *architecture)
+            {
+                return Err(XtaskError::InvalidBundle(format!(
+                    "{} doesn't contain the {} architecture ({})",
+                    executable.display(),
+                    architecture,
+                    lipo_info.trim()
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Strip debug symbols from the binaries of the plugins built by this invocation.
+/// The binaries are stripped after lipo and CMake produced them,
+/// so on macOS, all architectures of a universal binary are stripped.
+fn strip_plugins(plugins: &[PathBuf]) -> Result<(), XtaskError> {
+    if cfg!(windows) {
+        // MSVC keeps debug info in separate .pdb files, which aren't part of the plugins
+        info!("Nothing to strip on Windows");
+        return Ok(());
+    }
+
+    for plugin in plugins {
+        for binary in plugin_binaries(plugin)? {
+            let status = if cfg!(target_os = "macos") {
+                // -x keeps global symbols like clap_entry
+                Command::new("strip").arg("-x").arg(&binary).status()?
+            } else {
+                Command::new("strip")
+                    .arg("--strip-unneeded")
+                    .arg(&binary)
+                    .status()?
+            };
+
+            if !status.success() {
+                return Err(XtaskError::StripFailed(binary));
+            }
+        }
+
+        // stripping invalidates the code signature,
+        // without which Apple Silicon Macs refuse to load the plugin
+        if cfg!(target_os = "macos") && plugin.is_dir() {
+            let status = Command::new("codesign")
+                .args(["--force", "--sign", "-"])
+                .arg(plugin)
+                .status()?;
+
+            if !status.success() {
+                return Err(XtaskError::CodesignFailed(plugin.clone()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the binaries of a plugin, which is either a single file (e.g. a .clap on Linux)
+/// or a bundle containing the binaries in Contents/MacOS or Contents/<arch>-linux.
+fn plugin_binaries(plugin: &Path) -> Result<Vec<PathBuf>, XtaskError> {
+    if plugin.is_file() {
+        return Ok(vec![plugin.to_path_buf()]);
+    }
+
+    let contents_dir = plugin.join("Contents");
+    if !contents_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut binaries = Vec::new();
+    for entry in fs::read_dir(contents_dir)? {
+        let dir = entry?.path();
+        let Some(dir_name) = dir.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+
+        if dir.is_dir() && (dir_name == "MacOS" || dir_name.ends_with("-linux")) {
+            for binary in fs::read_dir(&dir)? {
+                let binary = binary?.path();
+                if binary.is_file() {
+                    binaries.push(binary);
                 }
             }
         }
+    }
+
+    Ok(binaries)
+}
+
+/// Open a directory in Finder, Explorer or the desktop's default file manager.
+/// The build already succeeded at this point, so failing to do so is only a note.
+fn reveal_in_file_manager(dir: &Path) {
+    // there's nobody to show a window to in CI or over SSH without a display
+    let headless = std::env::var_os("CI").is_some()
+        || (cfg!(target_os = "linux")
+            && std::env::var_os("DISPLAY").is_none()
+            && std::env::var_os("WAYLAND_DISPLAY").is_none());
+    if headless {
+        println!("Not revealing the plugins, as there is no display available");
+        return;
+    }
+
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
     } else {
-        // On macOS, files are output directly in the asset output directory.
-        // it's a sensible default for Linux as well
-        copy_dir_recursive(source_dir, dest_dir)?;
+        "xdg-open"
+    };
+
+    // explorer reports a failure exit code even when it opened the directory,
+    // so only failing to launch the file manager at all is reported
+    if let Err(e) = Command::new(opener).arg(dir).spawn() {
+        println!("Could not open the plugin directory with {}: {}", opener, e);
+    }
+}
+
+/// Whether plugins are installed for the current user only, or for all users
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum InstallScope {
+    User,
+    System,
+}
+
+/// Copy the plugin files and bundles built by this invocation
+/// to the plugin directories of the given scope
+fn install_plugins(plugins: &[PathBuf], scope: InstallScope) -> Result<(), XtaskError> {
+    for source_path in plugins {
+        let Some(extension) = source_path.extension().and_then(|e| e.to_str()) else {
+            continue;
+        };
+        let Some(install_dir) = plugin_install_dir(extension, scope) else {
+            continue;
+        };
+
+        let dest_path = install_dir.join(source_path.file_name().unwrap());
+
+        install_plugin(source_path, &install_dir, &dest_path).map_err(|e| match e {
+            XtaskError::Io(io_error) if io_error.kind() == std::io::ErrorKind::PermissionDenied => {
+                XtaskError::InstallPermissionDenied(install_dir.clone())
+            }
+            e => e,
+        })?;
+
+        info!("Installed {}", dest_path.display());
     }
 
     Ok(())
 }
 
+/// Copy a single plugin file or bundle into the install directory
+fn install_plugin(
+    source_path: &Path,
+    install_dir: &Path,
+    dest_path: &Path,
+) -> Result<(), XtaskError> {
+    fs::create_dir_all(install_dir)?;
+
+    // replace previously installed versions entirely,
+    // so that no stale files remain inside bundles
+    if dest_path.is_dir() {
+        fs::remove_dir_all(dest_path)?;
+    }
+
+    if source_path.is_dir() {
+        copy_dir_recursive(source_path, dest_path)?;
+    } else {
+        fs::copy(source_path, dest_path)?;
+    }
+
+    Ok(())
+}
+
+/// Get the directory that plugins with the given file extension are installed to,
+/// or None if the extension isn't a plugin format supported on this platform.
+///
+/// System-wide installs are handled by CMake except on Windows,
+/// so only the Windows system directories are known here.
+fn plugin_install_dir(extension: &str, scope: InstallScope) -> Option<PathBuf> {
+    if scope == InstallScope::System {
+        if !cfg!(windows) {
+            return None;
+        }
+
+        let common_dir = PathBuf::from(std::env::var_os("COMMONPROGRAMFILES")?);
+        return match extension {
+            "clap" => Some(common_dir.join("CLAP")),
+            "vst3" => Some(common_dir.join("VST3")),
+            _ => None,
+        };
+    }
+
+    if cfg!(target_os = "macos") {
+        let plugins_dir = PathBuf::from(std::env::var_os("HOME")?).join("Library/Audio/Plug-Ins");
+        match extension {
+            "clap" => Some(plugins_dir.join("CLAP")),
+            "vst3" => Some(plugins_dir.join("VST3")),
+            "component" => Some(plugins_dir.join("Components")),
+            _ => None,
+        }
+    } else if cfg!(windows) {
+        let common_dir = PathBuf::from(std::env::var_os("LOCALAPPDATA")?).join("Programs\\Common");
+        match extension {
+            "clap" => Some(common_dir.join("CLAP")),
+            "vst3" => Some(common_dir.join("VST3")),
+            _ => None,
+        }
+    } else {
+        let home_dir = PathBuf::from(std::env::var_os("HOME")?);
+        match extension {
+            "clap" => Some(home_dir.join(".clap")),
+            "vst3" => Some(home_dir.join(".vst3")),
+            _ => None,
+        }
+    }
+}
+
 /// Copy all files and directories recursively
-fn copy_dir_recursive(source: &Path, dest: &Path) -> Result<(), Box<dyn std::error::Error>> {
+fn copy_dir_recursive(source: &Path, dest: &Path) -> Result<(), XtaskError> {
     if !dest.exists() {
         fs::create_dir_all(dest)?;
     }
@@ -365,6 +1325,14 @@ fn copy_dir_recursive(source: &Path, dest: &Path) -> Result<(), Box<dyn std::err
         let path = entry.path();
         let dest_path = dest.join(path.file_name().unwrap());
 
+        // bundles may contain symlinks (e.g. Versions/Current),
+        // which must stay links for code signatures to remain valid
+        #[cfg(unix)]
+        if fs::symlink_metadata(&path)?.file_type().is_symlink() {
+            copy_symlink(&path, &dest_path)?;
+            continue;
+        }
+
         if path.is_dir() {
             copy_dir_recursive(&path, &dest_path)?;
         } else {
@@ -375,11 +1343,47 @@ fn copy_dir_recursive(source: &Path, dest: &Path) -> Result<(), Box<dyn std::err
     Ok(())
 }
 
-/// Get the project root directory
+/// Recreate a symlink pointing to the same target, instead of copying what it points to
+#[cfg(unix)]
+fn copy_symlink(source: &Path, dest: &Path) -> Result<(), XtaskError> {
+    let target = fs::read_link(source)?;
+
+    // replace whatever a previous build left at the destination
+    if let Ok(metadata) = fs::symlink_metadata(dest) {
+        if metadata.is_dir() {
+            fs::remove_dir_all(dest)?;
+        } else {
+            fs::remove_file(dest)?;
+        }
+    }
+
+    std::os::unix::fs::symlink(target, dest)?;
+
+    Ok(())
+}
+
+/// Get the project root directory, i.e. the closest directory
+/// containing a Cargo.toml with a [workspace] table, starting from the current directory.
+/// This also works for an xtask binary that was moved out of the workspace.
+/// Falls back to the parent directory of the xtask crate.
 fn project_root() -> PathBuf {
-    Path::new(&env!("CARGO_MANIFEST_DIR"))
-        .ancestors()
-        .nth(1)
-        .unwrap()
-        .to_path_buf()
+    let workspace_root = std::env::current_dir().ok().and_then(|dir| {
+        dir.ancestors()
+            .find(|dir| is_workspace_root(dir))
+            .map(Path::to_path_buf)
+    });
+
+    workspace_root.unwrap_or_else(|| {
+        Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .ancestors()
+            .nth(1)
+            .unwrap()
+            .to_path_buf()
+    })
+}
+
+/// Whether the directory contains a Cargo.toml declaring a workspace
+fn is_workspace_root(dir: &Path) -> bool {
+    fs::read_to_string(dir.join("Cargo.toml"))
+        .is_ok_and(|manifest| manifest.lines().any(|line| line.trim() == "[workspace]"))
 }
@@ -0,0 +1,101 @@
+//! End-to-end test that builds the gain example with xtask
+//! and checks the resulting CLAP plugin with clap-validator
+//! (https://github.com/free-audio/clap-validator).
+//!
+//! Building requires CMake and a C++ toolchain and takes a while, so the test is ignored by default.
+//! Run it with `cargo test -p xtask -- --ignored`.
+//! clap-validator is looked up on the PATH, or at the path given in the CLAP_VALIDATOR
+//! environment variable. If it can't be found, the test fails,
+//! so that a CI job running the ignored tests can't pass without validating anything.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The counts at the end of clap-validator's output,
+/// e.g. "36 tests run, 22 passed, 0 failed, 14 skipped, 0 warnings".
+#[derive(Debug, PartialEq, Eq)]
+struct ValidatorSummary {
+    passed: u32,
+    failed: u32,
+}
+
+/// Finds the summary line in clap-validator's output.
+fn parse_summary(output: &str) -> Option<ValidatorSummary> {
+    let line = output
+        .lines()
+        .rev()
+        .find(|line| line.contains("tests run"))?;
+
+    let count = |label: &str| {
+        line.split(',').find_map(|part| {
+            let (count, name) = part.trim().split_once(' ')?;
+            (name == label).then_some(count)?.parse().ok()
+        })
+    };
+
+    Some(ValidatorSummary {
+        passed: count("passed")?,
+        failed: count("failed")?,
+    })
+}
+
+#[test]
+fn summary_is_parsed() {
+    let output = "\
+Running 36 tests for plugin 'free-audio.clap.rust-gain-example.halver'...
+
+36 tests run, 22 passed, 0 failed, 14 skipped, 0 warnings
+";
+    assert_eq!(
+        parse_summary(output),
+        Some(ValidatorSummary {
+            passed: 22,
+            failed: 0
+        })
+    );
+
+    assert_eq!(parse_summary("1 test run"), None);
+    assert_eq!(parse_summary(""), None);
+}
+
+#[test]
+#[ignore = "builds the gain example with CMake, run with --ignored"]
+fn gain_example_passes_clap_validator() {
+    let validator =
+        std::env::var_os("CLAP_VALIDATOR").unwrap_or_else(|| OsString::from("clap-validator"));
+    assert!(
+        Command::new(&validator).arg("--version").output().is_ok(),
+        "clap-validator was not found at {:?}. Install it, or set CLAP_VALIDATOR to its path",
+        validator
+    );
+
+    // Build into a separate directory, so that the test doesn't pick up stale plugins
+    let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("clap-validator");
+    let _ = std::fs::remove_dir_all(&out_dir);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_xtask"))
+        .args(["build", "gain-example", "--quiet", "--out-dir"])
+        .arg(&out_dir)
+        .status()
+        .expect("Failed to run xtask");
+    assert!(status.success(), "Building the gain example failed");
+
+    let plugin = out_dir.join("gain-example.clap");
+    assert!(plugin.exists(), "No plugin at {}", plugin.display());
+
+    let output = Command::new(&validator)
+        .arg("validate")
+        .arg(&plugin)
+        .output()
+        .expect("Failed to run clap-validator");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let summary = parse_summary(&stdout)
+        .unwrap_or_else(|| panic!("No summary in clap-validator's output:\n{stdout}"));
+    assert_eq!(
+        summary.failed, 0,
+        "clap-validator reported failures:\n{stdout}"
+    );
+    assert!(summary.passed > 0, "clap-validator ran no tests:\n{stdout}");
+}